@@ -1,6 +1,147 @@
-use libocr::lexer::Lexer;
+use std::process::ExitCode;
 
-fn main() {
-    let input = "x = 13";
-    let mut l = Lexer::new(input);
+use libocr::diagnostics::Diagnostic;
+use libocr::format::format;
+use libocr::format::format_lenient;
+use libocr::interpreter::Interpreter;
+use libocr::parser::parse_with_recovery;
+
+const USAGE: &str = "usage: ocrlang [--seed <number>] [--json] <file>
+       ocrlang fmt [--check] [--any-case-keywords] <file>...";
+
+fn main() -> ExitCode {
+    let mut path = None;
+    let mut seed = None;
+    let mut json = false;
+    let mut args = std::env::args().skip(1).peekable();
+    if args.next_if_eq("fmt").is_some() {
+        return fmt(args);
+    }
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => match args.next().map(|s| s.parse::<u64>()) {
+                Some(Ok(s)) => seed = Some(s),
+                _ => {
+                    eprintln!("error: --seed needs a whole number\n{}", USAGE);
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--json" => json = true,
+            _ if path.is_none() => path = Some(arg),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let path = match path {
+        Some(p) => p,
+        None => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let input = match std::fs::read_to_string(&path) {
+        Ok(i) => i,
+        Err(e) => {
+            eprintln!("error: could not read {}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if json {
+        return dump_json(&input);
+    }
+
+    let (prog, errors) = parse_with_recovery(&input);
+    if !errors.is_empty() {
+        for e in &errors {
+            eprintln!("{}", Diagnostic::from(e).render(&path, &input));
+        }
+        return ExitCode::FAILURE;
+    }
+    let mut interpreter = Interpreter::new();
+    if let Some(s) = seed {
+        interpreter.set_random_seed(s);
+    }
+    if let Err(e) = interpreter.run(&prog) {
+        eprintln!("runtime error: {}", e);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Format each file in place, or with `--check` only report the ones which
+/// aren't formatted. With `--any-case-keywords`, keywords in any case are
+/// rewritten in their usual case.
+fn fmt(args: impl Iterator<Item = String>) -> ExitCode {
+    let mut check = false;
+    let mut any_case_keywords = false;
+    let mut paths = vec![];
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--any-case-keywords" => any_case_keywords = true,
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    }
+
+    let mut success = true;
+    for path in &paths {
+        let input = match std::fs::read_to_string(path) {
+            Ok(i) => i,
+            Err(e) => {
+                eprintln!("error: could not read {}: {}", path, e);
+                success = false;
+                continue;
+            }
+        };
+        let formatted = if any_case_keywords {
+            format_lenient(&input)
+        } else {
+            format(&input)
+        };
+        let formatted = match formatted {
+            Ok(f) => f,
+            Err(errors) => {
+                for e in &errors {
+                    eprintln!("{}", Diagnostic::from(e).render(path, &input));
+                }
+                success = false;
+                continue;
+            }
+        };
+        if formatted == input {
+            continue;
+        }
+        if check {
+            eprintln!("{} is not formatted", path);
+            success = false;
+        } else if let Err(e) = std::fs::write(path, formatted) {
+            eprintln!("error: could not write {}: {}", path, e);
+            success = false;
+        }
+    }
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Print the parse result, errors and all, as JSON for other tools to read
+#[cfg(feature = "serde")]
+fn dump_json(input: &str) -> ExitCode {
+    println!("{}", libocr::json::to_json(input));
+    ExitCode::SUCCESS
+}
+
+#[cfg(not(feature = "serde"))]
+fn dump_json(_input: &str) -> ExitCode {
+    eprintln!("error: --json needs ocrlang to be built with the serde feature");
+    ExitCode::FAILURE
 }
@@ -0,0 +1,15 @@
+//! A lossless concrete syntax tree. Every token, along with the whitespace and
+//! comments before it, is kept in a tree of nodes which follows the AST, so
+//! the source can be put back together byte for byte with `to_string`. This is
+//! meant for tools like formatters and refactorings which have to leave the
+//! parts of the code they don't touch alone.
+
+mod tree;
+
+#[cfg(test)]
+mod test;
+
+pub use tree::parse_lossless;
+pub use tree::SyntaxElement;
+pub use tree::SyntaxKind;
+pub use tree::SyntaxNode;
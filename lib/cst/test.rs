@@ -0,0 +1,157 @@
+use super::parse_lossless;
+use super::SyntaxElement;
+use super::SyntaxKind;
+use super::SyntaxNode;
+use crate::lexer::Token;
+use crate::lexer::Trivia;
+
+#[test]
+fn test_lossless_round_trip() {
+    let inputs = [
+        "",
+        "   \n\n",
+        "// just a comment",
+        "x=1",
+        "x  =  ( 1+2 )*3   // sum\n\n\ty = \"a // b\"\n",
+        "if x>1 then // big\n    print(x)\nelseif x == 1 then\nelse\n  // nothing\nendif\n",
+        "class Dog inherits Pet\n    private name\n    public procedure new(n:byVal)\n        name = n\n    endprocedure\nendclass",
+        "switch day:\n    case 1:\n        x = 1\n    default:\nendswitch\r\n",
+        "for i = 0 to 9 step 2\n    a[i, 1] = -i ^ 2\nnext i\ndo\nuntil NOT done",
+        // Broken code is kept as it was too
+        "x = (1 +\ny = \"never closed",
+        "x = é + 1\nwhile\n",
+    ];
+    for input in inputs {
+        let (tree, _) = parse_lossless(input);
+        assert_eq!(tree.to_string(), input);
+        assert_eq!(tree.kind, SyntaxKind::Program);
+    }
+}
+
+#[test]
+fn test_lossless_tree_structure() {
+    let input = "x = 1 + foo(2) // set x\nif x then\n    return\nendif";
+    let (tree, errors) = parse_lossless(input);
+    assert!(errors.is_empty());
+
+    let statements = tree.nodes().collect::<Vec<&SyntaxNode>>();
+    assert_eq!(
+        statements
+            .iter()
+            .map(|n| n.kind)
+            .collect::<Vec<SyntaxKind>>(),
+        [SyntaxKind::Assign, SyntaxKind::If]
+    );
+    assert_eq!(statements[0].to_string(), "x = 1 + foo(2)");
+    assert_eq!(
+        statements[0]
+            .nodes()
+            .map(|n| (n.kind, n.to_string()))
+            .collect::<Vec<(SyntaxKind, String)>>(),
+        [
+            (SyntaxKind::Identifier, "x".to_owned()),
+            (SyntaxKind::Infix, " 1 + foo(2)".to_owned()),
+        ]
+    );
+
+    // The comment is before the newline token which ends the assignment
+    let newline = tree.tokens()[8];
+    assert_eq!(newline.token.tok, Token::Newline);
+    assert_eq!(newline.leading, [
+        Trivia::Whitespace(" "),
+        Trivia::Comment("// set x")
+    ]);
+
+    let block = statements[1].nodes().last().unwrap();
+    assert_eq!(block.kind, SyntaxKind::Block);
+    assert_eq!(block.nodes().next().unwrap().kind, SyntaxKind::Return);
+}
+
+#[test]
+fn test_lossless_brackets() {
+    let (tree, errors) = parse_lossless("x = (1 + 2) * 3");
+    assert!(errors.is_empty());
+
+    let assign = tree.nodes().next().unwrap();
+    let tokens = assign
+        .children
+        .iter()
+        .filter_map(|c| match c {
+            SyntaxElement::Token(t) => Some(t.token.tok),
+            SyntaxElement::Node(_) => None,
+        })
+        .collect::<Vec<Token>>();
+    assert_eq!(tokens, [Token::Equals]);
+
+    let product = assign.nodes().nth(1).unwrap();
+    assert_eq!(product.kind, SyntaxKind::Infix);
+    assert_eq!(
+        product
+            .nodes()
+            .map(|n| (n.kind, n.to_string()))
+            .collect::<Vec<(SyntaxKind, String)>>(),
+        [
+            (SyntaxKind::Parenthesized, " (1 + 2)".to_owned()),
+            (SyntaxKind::IntegerLiteral, " 3".to_owned()),
+        ]
+    );
+
+    let brackets = product.nodes().next().unwrap();
+    let children = brackets
+        .children
+        .iter()
+        .map(|c| match c {
+            SyntaxElement::Token(t) => format!("{:?}", t.token.tok),
+            SyntaxElement::Node(n) => format!("{:?}", n.kind),
+        })
+        .collect::<Vec<String>>();
+    assert_eq!(children, ["LParenthasis", "Infix", "RParenthasis"]);
+
+    // The brackets of calls belong to the call
+    let (tree, _) = parse_lossless("print((x))");
+    let call = tree.nodes().next().unwrap().nodes().next().unwrap();
+    assert_eq!(call.kind, SyntaxKind::FunctionCall);
+    assert_eq!(
+        call.nodes()
+            .map(|n| (n.kind, n.to_string()))
+            .collect::<Vec<(SyntaxKind, String)>>(),
+        [(SyntaxKind::Parenthesized, "(x)".to_owned())]
+    );
+}
+
+#[test]
+fn test_lossless_broken_code() {
+    let kinds_and_text = |node: &SyntaxNode| {
+        node.nodes()
+            .map(|n| (n.kind, n.to_string()))
+            .collect::<Vec<(SyntaxKind, String)>>()
+    };
+
+    // The error node holds everything skipped, leaving only the end of the input
+    let (tree, errors) = parse_lossless("x = (");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(kinds_and_text(&tree), [(
+        SyntaxKind::Error,
+        "x = (".to_owned()
+    )]);
+    assert_eq!(tree.children.len(), 2);
+
+    // An error inside a block stays in the block, and the block ender after it
+    // still closes the block
+    let (tree, errors) = parse_lossless("if x then\nprint(1\nendif");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(kinds_and_text(&tree), [(
+        SyntaxKind::If,
+        "if x then\nprint(1\nendif".to_owned()
+    )]);
+    let if_stmt = tree.nodes().next().unwrap();
+    assert_eq!(kinds_and_text(if_stmt), [
+        (SyntaxKind::Identifier, " x".to_owned()),
+        (SyntaxKind::Block, "print(1".to_owned()),
+    ]);
+    let block = if_stmt.nodes().last().unwrap();
+    assert_eq!(kinds_and_text(block), [(
+        SyntaxKind::Error,
+        "print(1".to_owned()
+    )]);
+}
@@ -0,0 +1,313 @@
+use std::cmp::Reverse;
+use std::fmt::Display;
+
+use crate::lexer::Lexer;
+use crate::lexer::Span;
+use crate::lexer::Token;
+use crate::lexer::TriviaToken;
+use crate::parser::parse_with_recovery;
+use crate::parser::ParserError;
+use crate::syntax::walk_assign_target;
+use crate::syntax::walk_block;
+use crate::syntax::walk_class_method;
+use crate::syntax::walk_expr;
+use crate::syntax::walk_stmt;
+use crate::syntax::walk_switch_case;
+use crate::syntax::AssignTarget;
+use crate::syntax::AstNode;
+use crate::syntax::BlockStatement;
+use crate::syntax::ClassMethod;
+use crate::syntax::Expr;
+use crate::syntax::Stmt;
+use crate::syntax::SwitchCase;
+use crate::syntax::Visitor;
+
+/// What a node in the tree is, named after the AST node it comes from
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SyntaxKind {
+    Program,
+    Block,
+    SwitchCase,
+    Empty,
+
+    Assign,
+    Return,
+    ExpressionStatement,
+    If,
+    Function,
+    For,
+    While,
+    DoUntil,
+    Switch,
+    Error,
+    ArrayDeclaration,
+    Class,
+
+    Identifier,
+    Boolean,
+    Placeholder,
+    IntegerLiteral,
+    RealLiteral,
+    StringLiteral,
+    Prefix,
+    Infix,
+    FunctionCall,
+    Index,
+    New,
+    Super,
+    MemberAccess,
+    MethodCall,
+    /// Brackets around an expression, which aren't a node in the AST
+    Parenthesized,
+}
+impl From<&Stmt<'_>> for SyntaxKind {
+    fn from(value: &Stmt) -> Self {
+        match value {
+            Stmt::Assign(_) => Self::Assign,
+            Stmt::Return(_) => Self::Return,
+            Stmt::Expression(_) => Self::ExpressionStatement,
+            Stmt::If(_) => Self::If,
+            Stmt::Block(_) => Self::Block,
+            Stmt::Function(_) => Self::Function,
+            Stmt::For(_) => Self::For,
+            Stmt::While(_) => Self::While,
+            Stmt::DoUntil(_) => Self::DoUntil,
+            Stmt::Switch(_) => Self::Switch,
+            Stmt::Error(_) => Self::Error,
+            Stmt::ArrayDeclaration(_) => Self::ArrayDeclaration,
+            Stmt::Class(_) => Self::Class,
+            Stmt::Empty => Self::Empty,
+        }
+    }
+}
+impl From<&Expr<'_>> for SyntaxKind {
+    fn from(value: &Expr) -> Self {
+        match value {
+            Expr::Identifier(_) => Self::Identifier,
+            Expr::Boolean(_) => Self::Boolean,
+            Expr::Placeholder(_) => Self::Placeholder,
+            Expr::IntegerLiteral(_) => Self::IntegerLiteral,
+            Expr::RealLiteral(_) => Self::RealLiteral,
+            Expr::StringLiteral(_) => Self::StringLiteral,
+            Expr::Prefix(_) => Self::Prefix,
+            Expr::Infix(_) => Self::Infix,
+            Expr::FunctionCall(_) => Self::FunctionCall,
+            Expr::Index(_) => Self::Index,
+            Expr::New(_) => Self::New,
+            Expr::Super(_) => Self::Super,
+            Expr::MemberAccess(_) => Self::MemberAccess,
+            Expr::MethodCall(_) => Self::MethodCall,
+        }
+    }
+}
+impl From<&AssignTarget<'_>> for SyntaxKind {
+    fn from(value: &AssignTarget) -> Self {
+        match value {
+            AssignTarget::Identifier(_) => Self::Identifier,
+            AssignTarget::Index(_) => Self::Index,
+            AssignTarget::Member(_) => Self::MemberAccess,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SyntaxNode<'a> {
+    pub kind:     SyntaxKind,
+    /// The span of the AST node, which leaves out the trivia before its first
+    /// token. It is widened to take in any brackets it only partly covers.
+    pub span:     Span,
+    /// Tokens and nodes in the order they are in the source
+    pub children: Vec<SyntaxElement<'a>>,
+}
+impl<'a> SyntaxNode<'a> {
+    /// The nodes directly inside this one
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode<'a>> {
+        self.children.iter().filter_map(|c| match c {
+            SyntaxElement::Node(n) => Some(n),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Every token in the node and the nodes inside it, in order
+    pub fn tokens(&self) -> Vec<&TriviaToken<'a>> {
+        self.children
+            .iter()
+            .flat_map(|c| match c {
+                SyntaxElement::Node(n) => n.tokens(),
+                SyntaxElement::Token(t) => vec![t],
+            })
+            .collect()
+    }
+}
+/// The exact text the node was parsed from
+impl Display for SyntaxNode<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(n) => write!(f, "{}", n)?,
+                SyntaxElement::Token(t) => write!(f, "{}", t)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum SyntaxElement<'a> {
+    Node(SyntaxNode<'a>),
+    Token(TriviaToken<'a>),
+}
+
+/// Parse the input into a lossless tree, recovering from errors the same way
+/// as `parse_with_recovery` so that even broken code has a tree. Trivia
+/// belongs to the token after it, so comments at the end of the input are
+/// kept before the `Eof` token. Brackets around an expression are a
+/// `Parenthesized` node holding the expression.
+pub fn parse_lossless(input: &str) -> (SyntaxNode<'_>, Vec<ParserError>) {
+    let (prog, errors) = parse_with_recovery(input);
+    let mut collector = NodeCollector::default();
+    collector.visit_program(&prog);
+    let brackets = find_brackets(input);
+    // AST spans leave out brackets, so `(1 + 2) * 3` starts inside them.
+    // Widening each node over the brackets it overlaps, innermost first, makes
+    // the nodes nest.
+    let mut nodes = collector.nodes;
+    for (_, span) in &mut nodes {
+        for b in &brackets {
+            if b.start < span.start && span.start < b.end && span.end > b.end {
+                *span = Span {
+                    end: span.end,
+                    ..*b
+                };
+            } else if span.start < b.start && b.start < span.end && span.end < b.end {
+                span.end = b.end;
+            }
+        }
+    }
+    nodes.extend(brackets.iter().map(|b| (SyntaxKind::Parenthesized, *b)));
+    // A node's children start no earlier and end no later than it does, so
+    // sorting like this puts every node before its children
+    nodes.sort_by_key(|(_, span)| (span.start, Reverse(span.end)));
+    let mut nodes = nodes.into_iter().peekable();
+
+    let mut stack = vec![SyntaxNode {
+        kind:     SyntaxKind::Program,
+        span:     Span {
+            start: 0,
+            end:   input.len(),
+            line:  1,
+            col:   1,
+        },
+        children: vec![],
+    }];
+    let mut lexer = Lexer::new(input);
+    loop {
+        let tok = lexer.next_token_with_trivia();
+        let start = tok.token.span.start;
+        while let Some((kind, span)) = nodes.next_if(|(_, span)| span.start <= start) {
+            close_nodes_before(&mut stack, span.start);
+            stack.push(SyntaxNode {
+                kind,
+                span,
+                children: vec![],
+            });
+        }
+        close_nodes_before(&mut stack, start);
+
+        let eof = tok.token.tok == Token::Eof;
+        stack
+            .last_mut()
+            .unwrap()
+            .children
+            .push(SyntaxElement::Token(tok));
+        if eof {
+            break;
+        }
+    }
+    close_nodes_before(&mut stack, usize::MAX);
+    (stack.pop().unwrap(), errors)
+}
+
+/// The spans of the brackets around expressions, from `(` to `)`, the
+/// innermost first. Brackets after a name or `new` are those of a call or a
+/// declaration, like `f(x)`, so they are left out, as are any which aren't
+/// closed.
+fn find_brackets(input: &str) -> Vec<Span> {
+    let mut brackets = vec![];
+    let mut open = vec![];
+    let mut prev = Token::Newline;
+    let mut lexer = Lexer::new(input);
+    loop {
+        let tok = lexer.next_token_with_trivia().token;
+        match tok.tok {
+            Token::LParenthasis => {
+                let grouping = !matches!(prev, Token::Identifier(_) | Token::New);
+                open.push((tok.span, grouping));
+            }
+            Token::RParenthasis => {
+                if let Some((start, true)) = open.pop() {
+                    brackets.push(start.to(tok.span));
+                }
+            }
+            Token::Eof => break,
+            _ => {}
+        }
+        prev = tok.tok;
+    }
+    brackets.sort_by_key(|b| b.end - b.start);
+    brackets
+}
+
+/// Finish the nodes which end before the position, moving them into their
+/// parents. The root is never finished.
+fn close_nodes_before(stack: &mut Vec<SyntaxNode>, pos: usize) {
+    while stack.len() > 1 && stack.last().unwrap().span.end <= pos {
+        let node = stack.pop().unwrap();
+        stack
+            .last_mut()
+            .unwrap()
+            .children
+            .push(SyntaxElement::Node(node));
+    }
+}
+
+/// Finds the kind and span of every node in the AST
+#[derive(Default)]
+struct NodeCollector {
+    nodes: Vec<(SyntaxKind, Span)>,
+}
+impl<'a> Visitor<'a> for NodeCollector {
+    fn visit_stmt(&mut self, stmt: &'a Stmt<'a>) {
+        // Empty statements don't have any tokens to hold, and blocks are found
+        // by `visit_block`
+        if !matches!(stmt, Stmt::Empty | Stmt::Block(_)) {
+            self.nodes.push((stmt.into(), stmt.span()));
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_block(&mut self, block: &'a BlockStatement<'a>) {
+        self.nodes.push((SyntaxKind::Block, block.span));
+        walk_block(self, block);
+    }
+
+    fn visit_class_method(&mut self, method: &'a ClassMethod<'a>) {
+        self.nodes.push((SyntaxKind::Function, method.func.span));
+        walk_class_method(self, method);
+    }
+
+    fn visit_switch_case(&mut self, case: &'a SwitchCase<'a>) {
+        self.nodes.push((SyntaxKind::SwitchCase, case.span));
+        walk_switch_case(self, case);
+    }
+
+    fn visit_assign_target(&mut self, target: &'a AssignTarget<'a>) {
+        self.nodes.push((target.into(), target.span()));
+        walk_assign_target(self, target);
+    }
+
+    fn visit_expr(&mut self, expr: &'a Expr<'a>) {
+        self.nodes.push((expr.into(), expr.span()));
+        walk_expr(self, expr);
+    }
+}
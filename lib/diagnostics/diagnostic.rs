@@ -0,0 +1,81 @@
+use crate::lexer::Span;
+use crate::parser::ParserError;
+
+/// An error message tied to a place in the source code, which can be rendered
+/// in the style of rustc:
+///
+/// ```text
+/// error: expected `then`, found newline
+///  --> main.ocr:1:5
+///   |
+/// 1 | if x
+///   |     ^
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span:    Span,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Render the diagnostic with the line of `source` it points at. Spans
+    /// covering multiple lines are only underlined up to the end of their
+    /// first line.
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let mut out = format!("error: {}\n", self.message);
+
+        // The default span doesn't point at anything, so there is no snippet
+        if self.span.line == 0 {
+            out += &format!(" --> {}\n", file_name);
+            return out;
+        }
+
+        let line = source
+            .split('\n')
+            .nth(self.span.line - 1)
+            .unwrap_or_default()
+            .trim_end_matches('\r');
+        let line_no = self.span.line.to_string();
+        let gutter = " ".repeat(line_no.len());
+
+        // Tabs are kept so that the carets line up however wide they are shown
+        let indent: String = line
+            .chars()
+            .take(self.span.col - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = source
+            .as_bytes()
+            .get(self.span.start..self.span.end)
+            .unwrap_or_default()
+            .iter()
+            .take_while(|b| **b != b'\n')
+            // Don't count UTF-8 continuation bytes, so that the width is in
+            // characters
+            .filter(|b| (**b & 0xC0) != 0x80)
+            .count()
+            .max(1);
+
+        out += &format!(
+            "{}--> {}:{}:{}\n",
+            gutter, file_name, self.span.line, self.span.col
+        );
+        out += &format!("{} |\n", gutter);
+        out += &format!("{} | {}\n", line_no, line);
+        out += &format!("{} | {}{}\n", gutter, indent, "^".repeat(width));
+        out
+    }
+}
+
+impl From<&ParserError> for Diagnostic {
+    fn from(value: &ParserError) -> Self {
+        Self::new(value.to_string(), value.span())
+    }
+}
@@ -0,0 +1,6 @@
+mod diagnostic;
+
+#[cfg(test)]
+mod test;
+
+pub use diagnostic::Diagnostic;
@@ -0,0 +1,63 @@
+use super::Diagnostic;
+use crate::lexer::Span;
+use crate::parser::parse_from_string;
+
+#[test]
+fn test_render_parser_error() {
+    let input = "x = 1
+if x > 5
+    y = 2
+endif";
+    let err = parse_from_string(input).unwrap_err();
+    assert_eq!(
+        Diagnostic::from(&err).render("main.ocr", input),
+        "error: expected `then`, found newline
+ --> main.ocr:2:9
+  |
+2 | if x > 5
+  |         ^
+"
+    );
+}
+
+#[test]
+fn test_render_multi_character_span() {
+    let input = "for i = 0 to 3
+\tx = i
+next counter";
+    let err = parse_from_string(input).unwrap_err();
+    assert_eq!(
+        Diagnostic::from(&err).render("loop.ocr", input),
+        "error: the identifier after `next` doesn't match the loop counter
+ --> loop.ocr:3:6
+  |
+3 | next counter
+  |      ^^^^^^^
+"
+    );
+}
+
+#[test]
+fn test_render_keeps_tabs_and_wide_line_numbers() {
+    let input = "\n".repeat(9) + "\t\"é\" + )";
+    let err = parse_from_string(&input).unwrap_err();
+    assert_eq!(
+        Diagnostic::from(&err).render("tabs.ocr", &input),
+        "error: expected an expression, found `)`
+  --> tabs.ocr:10:8
+   |
+10 | \t\"é\" + )
+   | \t      ^
+"
+    );
+}
+
+#[test]
+fn test_render_without_span() {
+    assert_eq!(
+        Diagnostic::new("something went wrong", Span::default()).render("main.ocr", "x = 1"),
+        "error: something went wrong
+ --> main.ocr
+"
+    );
+}
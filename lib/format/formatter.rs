@@ -0,0 +1,357 @@
+use std::collections::VecDeque;
+
+use crate::lexer::Lexer;
+use crate::lexer::Span;
+use crate::parser::parse_from_lexer_with_recovery;
+use crate::parser::ParserError;
+use crate::syntax::AstNode;
+use crate::syntax::BlockStatement;
+use crate::syntax::ClassStatement;
+use crate::syntax::DoUntilStatement;
+use crate::syntax::ForStatement;
+use crate::syntax::FunctionStatement;
+use crate::syntax::IfStatement;
+use crate::syntax::PrettyPrint;
+use crate::syntax::Stmt;
+use crate::syntax::SwitchStatement;
+use crate::syntax::WhileStatement;
+
+/// Format the source code in the canonical style: blocks indented by four
+/// spaces, spaces around operators and at most one blank line between
+/// statements. Comments are kept where they were. Formatting code which is
+/// already formatted doesn't change it.
+///
+/// Code with syntax errors can't be formatted, so the errors are given back
+/// instead.
+pub fn format(input: &str) -> Result<String, Vec<ParserError>> {
+    format_lexed(input, Lexer::new(input))
+}
+
+/// Like `format`, but keywords written in any case are read as keywords and
+/// written in their usual case, so `EndIf` becomes `endif`. Names which are
+/// keywords in another case, like `Next`, are syntax errors.
+pub fn format_lenient(input: &str) -> Result<String, Vec<ParserError>> {
+    format_lexed(input, Lexer::new(input).with_any_case_keywords())
+}
+
+fn format_lexed<'a>(input: &'a str, lexer: Lexer<'a>) -> Result<String, Vec<ParserError>> {
+    let (prog, errors) = parse_from_lexer_with_recovery(lexer);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let mut f = Formatter::new(input);
+    for stmt in &prog.statements {
+        f.stmt(stmt);
+    }
+    // Comments after the last statement
+    f.comments_before(usize::MAX);
+    Ok(f.out)
+}
+
+/// A `//` comment, which the parser throws away so it has to be found in the
+/// source separately
+#[derive(Debug, Clone, Copy)]
+struct Comment<'a> {
+    line: usize,
+    text: &'a str,
+}
+
+/// Find every comment in the source, skipping over anything in string literals
+fn find_comments(input: &str) -> VecDeque<Comment<'_>> {
+    let mut comments = VecDeque::new();
+    let bytes = input.as_bytes();
+    let mut line = 1;
+    let mut in_string = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => line += 1,
+            b'"' => in_string = !in_string,
+            b'/' if !in_string && bytes.get(i + 1) == Some(&b'/') => {
+                let end = input[i..].find('\n').map_or(input.len(), |e| i + e);
+                comments.push_back(Comment {
+                    line,
+                    text: input[i..end].trim_end(),
+                });
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    comments
+}
+
+struct Formatter<'a> {
+    lines:    Vec<&'a str>,
+    /// The byte offset each line starts at
+    starts:   Vec<usize>,
+    comments: VecDeque<Comment<'a>>,
+    out:      String,
+    depth:    usize,
+
+    // Blank lines are kept between statements, but not at the start or end of a
+    // block, and only once for each line of the source
+    block_start: bool,
+    last_line:   usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            lines:       input.split('\n').collect(),
+            starts:      std::iter::once(0)
+                .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
+            comments:    find_comments(input),
+            out:         String::new(),
+            depth:       0,
+            block_start: true,
+            last_line:   0,
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::If(s) => self.if_stmt(s),
+            Stmt::For(s) => self.for_stmt(s),
+            Stmt::While(s) => self.while_stmt(s),
+            Stmt::DoUntil(s) => self.do_until(s),
+            Stmt::Switch(s) => self.switch(s),
+            Stmt::Function(s) => self.function(s, ""),
+            Stmt::Class(s) => self.class(s),
+            Stmt::Block(s) => self.block(s),
+            Stmt::Empty => {}
+            s => self.line(s.span().line, &s.pretty_print()),
+        }
+    }
+
+    fn block(&mut self, block: &BlockStatement) {
+        for stmt in &block.statements {
+            self.stmt(stmt);
+        }
+    }
+
+    fn if_stmt(&mut self, s: &IfStatement) {
+        let header = format!("if {} then", s.condition.pretty_print());
+        self.begin_block(s.span.line, &header);
+        self.block(&s.consequence);
+        for (condition, block) in &s.elseifs {
+            let header = format!("elseif {} then", condition.pretty_print());
+            self.middle_of_block(condition.span().line, &header);
+            self.block(block);
+        }
+        if let Some(alternative) = &s.alternative {
+            let line = self.keyword_line(block_line(alternative), "else");
+            self.middle_of_block(line, "else");
+            self.block(alternative);
+        }
+        self.end_block(self.end_line(s.span), "endif");
+    }
+
+    fn for_stmt(&mut self, s: &ForStatement) {
+        let step = match &s.step {
+            Some(step) => " step ".to_owned() + &step.pretty_print(),
+            None => String::new(),
+        };
+        let header = format!(
+            "for {} = {} to {}{}",
+            s.counter.get_ident(),
+            s.start.pretty_print(),
+            s.end.pretty_print(),
+            step
+        );
+        self.begin_block(s.span.line, &header);
+        self.block(&s.body);
+        let end = "next ".to_owned() + s.counter.get_ident();
+        self.end_block(self.end_line(s.span), &end);
+    }
+
+    fn while_stmt(&mut self, s: &WhileStatement) {
+        let header = "while ".to_owned() + &s.condition.pretty_print();
+        self.begin_block(s.span.line, &header);
+        self.block(&s.body);
+        self.end_block(self.end_line(s.span), "endwhile");
+    }
+
+    fn do_until(&mut self, s: &DoUntilStatement) {
+        self.begin_block(s.span.line, "do");
+        self.block(&s.body);
+        let end = "until ".to_owned() + &s.condition.pretty_print();
+        self.end_block(s.condition.span().line, &end);
+    }
+
+    fn switch(&mut self, s: &SwitchStatement) {
+        let header = format!("switch {}:", s.subject.pretty_print());
+        self.begin_block(s.span.line, &header);
+        // Each case is a block inside the switch, with its body a block inside that
+        for (i, case) in s.cases.iter().enumerate() {
+            let header = format!("case {}:", case.value.pretty_print());
+            if i == 0 {
+                self.begin_block(case.span.line, &header);
+            } else {
+                self.middle_of_block(case.span.line, &header);
+            }
+            self.block(&case.body);
+        }
+        if let Some(default) = &s.default {
+            let line = self.keyword_line(block_line(default), "default");
+            if s.cases.is_empty() {
+                self.begin_block(line, "default:");
+            } else {
+                self.middle_of_block(line, "default:");
+            }
+            self.block(default);
+        }
+        let end = self.end_line(s.span);
+        if !s.cases.is_empty() || s.default.is_some() {
+            self.dedent(end);
+        }
+        self.end_block(end, "endswitch");
+    }
+
+    /// Methods have their visibility written before them, which is the prefix
+    fn function(&mut self, f: &FunctionStatement, prefix: &str) {
+        let (keyword, end) = if f.is_procedure {
+            ("procedure", "endprocedure")
+        } else {
+            ("function", "endfunction")
+        };
+        let header = format!(
+            "{}{} {}({})",
+            prefix,
+            keyword,
+            f.ident.get_ident(),
+            f.params
+                .iter()
+                .map(|p| p.pretty_print())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        self.begin_block(f.span.line, &header);
+        self.block(&f.body);
+        self.end_block(self.end_line(f.span), end);
+    }
+
+    fn class(&mut self, c: &ClassStatement) {
+        let mut header = "class ".to_owned() + c.ident.get_ident();
+        if let Some(parent) = &c.parent {
+            header += &(" inherits ".to_owned() + parent.get_ident());
+        }
+        self.begin_block(c.span.line, &header);
+        // Attributes and methods are kept separately, but they're written back in
+        // the order they were in so that comments stay with them
+        let mut attributes = c.attributes.iter().peekable();
+        let mut methods = c.methods.iter().peekable();
+        loop {
+            let attribute_first = match (attributes.peek(), methods.peek()) {
+                (Some(a), Some(m)) => a.ident.span.start < m.func.span.start,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            if attribute_first {
+                let a = attributes.next().unwrap();
+                self.line(a.ident.span.line, &a.pretty_print());
+            } else {
+                let m = methods.next().unwrap();
+                self.function(&m.func, &(m.visibility.to_string() + " "));
+            }
+        }
+        self.end_block(self.end_line(c.span), "endclass");
+    }
+
+    /// Write the line which starts a block, everything after it is indented
+    fn begin_block(&mut self, line: usize, text: &str) {
+        self.line(line, text);
+        self.depth += 1;
+        self.block_start = true;
+    }
+
+    /// Write a line like `else` which ends one block and starts the next
+    fn middle_of_block(&mut self, line: usize, text: &str) {
+        self.dedent(line);
+        self.begin_block(line, text);
+    }
+
+    /// Write the line which ends a block, like `endif`
+    fn end_block(&mut self, line: usize, text: &str) {
+        self.dedent(line);
+        self.block_start = true;
+        self.line(line, text);
+    }
+
+    /// Leave the current block. Comments before the line which ends it are
+    /// still part of it, so they're indented with it.
+    fn dedent(&mut self, line: usize) {
+        self.comments_before(line);
+        self.depth -= 1;
+    }
+
+    /// Write a line of code from the given line of the source, along with any
+    /// comments before it or at the end of it
+    fn line(&mut self, line: usize, text: &str) {
+        self.comments_before(line);
+        self.start_line(line);
+        self.out += text;
+        if let Some(c) = self.comments.front().filter(|c| c.line == line) {
+            self.out += " ";
+            self.out += c.text;
+            self.comments.pop_front();
+        }
+        self.out.push('\n');
+    }
+
+    /// Write the comments from before the given line, each on its own line
+    fn comments_before(&mut self, line: usize) {
+        while let Some(c) = self.comments.front().filter(|c| c.line < line).copied() {
+            self.comments.pop_front();
+            self.start_line(c.line);
+            self.out += c.text;
+            self.out.push('\n');
+        }
+    }
+
+    fn start_line(&mut self, line: usize) {
+        let after_blank =
+            line > 1 && line > self.last_line + 1 && self.lines[line - 2].trim().is_empty();
+        if after_blank && !self.block_start && !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.block_start = false;
+        self.last_line = self.last_line.max(line);
+        self.out += &"    ".repeat(self.depth);
+    }
+
+    /// The line of the last character in the span, which is where the keyword
+    /// ending a block is
+    fn end_line(&self, span: Span) -> usize {
+        self.starts.partition_point(|s| *s < span.end)
+    }
+
+    /// Keywords like `else` aren't kept in the tree, so the line they are on is
+    /// found by looking backwards from the start of the block after them
+    fn keyword_line(&self, from: usize, keyword: &str) -> usize {
+        (1..=from)
+            .rev()
+            .find(|l| {
+                let word = self.lines[l - 1]
+                    .trim_start()
+                    .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .next()
+                    .unwrap_or_default();
+                word.eq_ignore_ascii_case(keyword)
+            })
+            .unwrap_or(from)
+    }
+}
+
+/// The line a block starts on, for an empty block that's the line of whatever
+/// ends it
+fn block_line(block: &BlockStatement) -> usize {
+    match block.statements.first() {
+        Some(s) => s.span().line,
+        None => block.span.line,
+    }
+}
@@ -0,0 +1,7 @@
+mod formatter;
+
+#[cfg(test)]
+mod test;
+
+pub use formatter::format;
+pub use formatter::format_lenient;
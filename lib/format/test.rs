@@ -0,0 +1,213 @@
+use super::format;
+use super::format_lenient;
+use crate::parser::ParserError;
+
+#[test]
+fn test_format_blocks() {
+    let input = "x=1
+if x>0 then
+y=x*2
+elseif x==0 then
+y=0
+else
+y=-x
+endif
+for i=0 to 10 step 2
+while i<5
+i=i+1
+endwhile
+next i
+do
+x=x DIV 2
+until x<1
+";
+    let expected = "x = 1
+if x > 0 then
+    y = x * 2
+elseif x == 0 then
+    y = 0
+else
+    y = -x
+endif
+for i = 0 to 10 step 2
+    while i < 5
+        i = i + 1
+    endwhile
+next i
+do
+    x = x DIV 2
+until x < 1
+";
+    assert_eq!(format(input).unwrap(), expected);
+}
+
+#[test]
+fn test_format_switch_and_class() {
+    let input = "switch day:
+case 1:
+x = 1
+case 2:
+default:
+x = 0
+endswitch
+class Dog inherits Pet
+public procedure new(name)
+super.new(name)
+endprocedure
+private age
+private function older(a:byVal, b:byRef)
+return age>b
+endfunction
+endclass
+";
+    let expected = "switch day:
+    case 1:
+        x = 1
+    case 2:
+    default:
+        x = 0
+endswitch
+class Dog inherits Pet
+    public procedure new(name)
+        super.new(name)
+    endprocedure
+    private age
+    private function older(a:byVal, b:byRef)
+        return age > b
+    endfunction
+endclass
+";
+    assert_eq!(format(input).unwrap(), expected);
+}
+
+#[test]
+fn test_format_comments() {
+    let input = "// Count down
+x = 10 // start
+
+// Loop until done
+while x > 0 // still going
+    // Step down
+    x = x - 1
+    // End of the body
+endwhile // done
+if x == 0 then
+    print(\"done // not a comment\")
+else // never
+    // Nothing else to do
+    x = 0
+endif
+// The end
+";
+    assert_eq!(format(input).unwrap(), input);
+}
+
+#[test]
+fn test_format_keyword_case() {
+    let input = "IF x And NOT y THEN
+Print(x Mod 2)
+EndIf
+";
+    assert_eq!(
+        format_lenient(input).unwrap(),
+        "if x AND NOT y then
+    Print(x MOD 2)
+endif
+"
+    );
+    assert!(format(input).is_err());
+
+    // Keywords in another case are only names when lexing strictly
+    let input = "Next = 3\nprint(Next)\n";
+    assert_eq!(format(input).unwrap(), input);
+    assert!(format_lenient(input).is_err());
+}
+
+#[test]
+fn test_format_blank_lines() {
+    let input = "\n\nx = 1
+
+
+
+y = 2
+if x then
+
+    y = 3
+
+endif
+
+
+";
+    assert_eq!(
+        format(input).unwrap(),
+        "x = 1
+
+y = 2
+if x then
+    y = 3
+endif
+"
+    );
+}
+
+#[test]
+fn test_format_brackets() {
+    let input = "x = ((a + b)) * (c - (d - e))
+y = a + (b * c)
+z = NOT (a AND b) OR (NOT c)
+w = (2 ^ 3) ^ 2 + 2 ^ (3 ^ 2) + (-2) ^ 2
+";
+    assert_eq!(
+        format(input).unwrap(),
+        "x = (a + b) * (c - (d - e))
+y = a + b * c
+z = NOT (a AND b) OR NOT c
+w = (2 ^ 3) ^ 2 + 2 ^ 3 ^ 2 + (-2) ^ 2
+"
+    );
+}
+
+#[test]
+fn test_format_is_idempotent() {
+    let input = "// Bubble sort
+procedure sort(items:byRef, n)
+for i=0 to n-2   // passes
+for j = 0 to n-i-2
+if items[j]>items[j+1] then
+
+temp=items[j]
+items[j]=items[j+1]
+items[j+1]=temp
+endif
+next j
+next i
+endprocedure
+
+array nums[3]
+nums[0]=3 // unsorted
+sort(nums, 3)
+";
+    let formatted = format(input).unwrap();
+    assert_eq!(format(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn test_format_syntax_errors() {
+    let errors = format("x = (1 +\ny = 2").unwrap_err();
+    assert!(matches!(errors[..], [ParserError::ExpectedExpression(_)]));
+}
+
+#[test]
+fn test_format_keeps_number_literals() {
+    let input = "x=123456789012345678901234567890.0
+y = 007+1.50
+z = 2.5e3
+";
+    assert_eq!(
+        format(input).unwrap(),
+        "x = 123456789012345678901234567890.0
+y = 007 + 1.50
+z = 2.5e3
+"
+    );
+}
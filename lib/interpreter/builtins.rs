@@ -0,0 +1,198 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::files::file_error;
+use super::FileSystem;
+use super::Io;
+use super::OpenFile;
+use super::Rng;
+use super::RuntimeError;
+use super::Value;
+use super::ValueType;
+
+/// Everything outside of the program that builtins can use
+pub struct Host<'h> {
+    pub io:  &'h mut dyn Io,
+    pub fs:  &'h mut dyn FileSystem,
+    pub rng: &'h mut Rng,
+}
+
+/// A function which is always available, unless the program declares its own
+/// function with the same name. The arguments have already been evaluated.
+pub type Builtin = for<'a> fn(&mut Host, Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError>;
+
+pub fn lookup(name: &str) -> Option<Builtin> {
+    match name {
+        "print" => Some(print),
+        "input" => Some(input),
+        "open" => Some(open),
+        "newFile" => Some(new_file),
+        "str" => Some(to_str),
+        "int" => Some(to_int),
+        "float" | "real" => Some(to_real),
+        "bool" => Some(to_bool),
+        "ASC" => Some(asc),
+        "CHR" => Some(chr),
+        "random" => Some(random),
+        _ => None,
+    }
+}
+
+/// Writes every argument on one line, separated by spaces
+fn print<'a>(host: &mut Host, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    let line = args
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<String>>()
+        .join(" ");
+    host.io.write_line(&line).map_err(io_error)?;
+    Ok(Value::Nothing)
+}
+
+fn input<'a>(host: &mut Host, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    expect_args("input", &args, 1)?;
+    match host.io.read_line(&args[0].to_string()).map_err(io_error)? {
+        Some(line) => Ok(Value::String(line)),
+        None => Err(RuntimeError::EndOfInput),
+    }
+}
+
+fn open<'a>(host: &mut Host, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    expect_args("open", &args, 1)?;
+    let file = OpenFile::open(host.fs, &args[0].to_string())?;
+    Ok(Value::File(Rc::new(RefCell::new(file))))
+}
+
+/// Creates an empty file, replacing the file if it already exists
+fn new_file<'a>(host: &mut Host, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    expect_args("newFile", &args, 1)?;
+    let path = args[0].to_string();
+    host.fs.write(&path, "").map_err(|e| file_error(&path, e))?;
+    Ok(Value::Nothing)
+}
+
+fn to_str<'a>(_: &mut Host, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    let value = single_arg("str", args)?;
+    Ok(Value::String(value.to_string()))
+}
+
+/// Reals are truncated towards zero, strings have to be a whole number
+fn to_int<'a>(_: &mut Host, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    match single_arg("int", args)? {
+        Value::Integer(i) => Ok(Value::Integer(i)),
+        Value::Real(r) if r.abs() < i128::MAX as f64 => Ok(Value::Integer(r.trunc() as i128)),
+        Value::String(s) => match s.trim().parse() {
+            Ok(i) => Ok(Value::Integer(i)),
+            Err(_) => Err(invalid_conversion(&s, ValueType::Integer)),
+        },
+        v => Err(invalid_conversion(&v.to_string(), ValueType::Integer)),
+    }
+}
+
+fn to_real<'a>(_: &mut Host, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    match single_arg("real", args)? {
+        Value::Integer(i) => Ok(Value::Real(i as f64)),
+        Value::Real(r) => Ok(Value::Real(r)),
+        Value::String(s) => match s.trim().parse::<f64>() {
+            Ok(r) if r.is_finite() => Ok(Value::Real(r)),
+            _ => Err(invalid_conversion(&s, ValueType::Real)),
+        },
+        v => Err(invalid_conversion(&v.to_string(), ValueType::Real)),
+    }
+}
+
+/// Strings have to be `true` or `false`, ignoring case
+fn to_bool<'a>(_: &mut Host, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    match single_arg("bool", args)? {
+        Value::Boolean(b) => Ok(Value::Boolean(b)),
+        Value::String(s) if s.trim().eq_ignore_ascii_case("true") => Ok(Value::Boolean(true)),
+        Value::String(s) if s.trim().eq_ignore_ascii_case("false") => Ok(Value::Boolean(false)),
+        v => Err(invalid_conversion(&v.to_string(), ValueType::Boolean)),
+    }
+}
+
+/// The character code of a string with exactly one character in it
+fn asc<'a>(_: &mut Host, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    let value = single_arg("ASC", args)?;
+    if let Value::String(s) = &value {
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(Value::Integer(c as i128));
+        }
+    }
+    Err(invalid_conversion(&value.to_string(), ValueType::Integer))
+}
+
+fn chr<'a>(_: &mut Host, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    match single_arg("CHR", args)? {
+        Value::Integer(i) => u32::try_from(i)
+            .ok()
+            .and_then(char::from_u32)
+            .map(|c| Value::String(c.to_string()))
+            .ok_or_else(|| invalid_conversion(&i.to_string(), ValueType::String)),
+        v => Err(RuntimeError::ExpectedInteger((&v).into())),
+    }
+}
+
+/// `random(1, 6)` gives an integer from 1 to 6 inclusive. If either bound is
+/// a real then the result is a real from the lower bound up to, but not
+/// including, the upper bound.
+fn random<'a>(host: &mut Host, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    expect_args("random", &args, 2)?;
+    match (&args[0], &args[1]) {
+        (Value::Integer(low), Value::Integer(high)) => {
+            if low > high {
+                return Err(RuntimeError::InvalidRandomRange);
+            }
+            let width = high.abs_diff(*low).checked_add(1);
+            let r = ((host.rng.next_u64() as u128) << 64) | host.rng.next_u64() as u128;
+            let offset = match width {
+                Some(w) => r % w,
+                None => r,
+            };
+            Ok(Value::Integer(low.wrapping_add(offset as i128)))
+        }
+        (low, high) => {
+            let (low, high) = (number(low)?, number(high)?);
+            if low > high {
+                return Err(RuntimeError::InvalidRandomRange);
+            }
+            Ok(Value::Real(low + (high - low) * host.rng.next_f64()))
+        }
+    }
+}
+
+fn number(value: &Value) -> Result<f64, RuntimeError> {
+    match value {
+        Value::Integer(i) => Ok(*i as f64),
+        Value::Real(r) => Ok(*r),
+        v => Err(RuntimeError::ExpectedNumber(v.into())),
+    }
+}
+
+fn single_arg<'a>(func: &str, args: Vec<Value<'a>>) -> Result<Value<'a>, RuntimeError> {
+    expect_args(func, &args, 1)?;
+    Ok(args
+        .into_iter()
+        .next()
+        .expect("there is exactly one argument"))
+}
+
+fn invalid_conversion(value: &str, to: ValueType) -> RuntimeError {
+    RuntimeError::InvalidConversion(value.to_owned(), to)
+}
+
+fn expect_args(func: &str, args: &[Value], expected: usize) -> Result<(), RuntimeError> {
+    if args.len() != expected {
+        return Err(RuntimeError::WrongArgumentCount {
+            func: func.to_owned(),
+            expected,
+            found: args.len(),
+        });
+    }
+    Ok(())
+}
+
+fn io_error(err: std::io::Error) -> RuntimeError {
+    RuntimeError::Io(err.to_string())
+}
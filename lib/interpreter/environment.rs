@@ -0,0 +1,120 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use super::Array;
+use super::Value;
+
+/// A single scope of variables. Each function call gets its own environment
+/// which is enclosed by the environment the function was declared in.
+#[derive(Default, Debug)]
+pub struct Environment<'a> {
+    store:   HashMap<String, Binding<'a>>,
+    outer:   Option<Rc<RefCell<Environment<'a>>>>,
+    /// Names declared with `global` in this scope, which are assigned to in the
+    /// global environment instead of this one
+    globals: HashSet<String>,
+}
+
+#[derive(Debug)]
+enum Binding<'a> {
+    Value(Value<'a>),
+    /// A `byRef` parameter, which reads and writes the caller's variable
+    Reference(Place<'a>),
+}
+
+/// Somewhere a value is stored, which a `byRef` parameter refers to
+#[derive(Debug, Clone)]
+pub(crate) enum Place<'a> {
+    /// A variable, or an attribute of an object, in the environment which
+    /// holds it
+    Variable(Rc<RefCell<Environment<'a>>>, String),
+    Element(Array<'a>, usize),
+}
+impl<'a> Place<'a> {
+    fn get(&self) -> Value<'a> {
+        match self {
+            Self::Variable(env, name) => env
+                .borrow()
+                .get(name)
+                .expect("a place always refers to a defined variable"),
+            Self::Element(array, index) => array.borrow()[*index].clone(),
+        }
+    }
+
+    fn set(&self, value: Value<'a>) {
+        match self {
+            Self::Variable(env, name) => env.borrow_mut().define(name, value),
+            Self::Element(array, index) => array.borrow_mut()[*index] = value,
+        }
+    }
+}
+
+impl<'a> Environment<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_enclosed(outer: Rc<RefCell<Environment<'a>>>) -> Self {
+        Self {
+            store:   HashMap::new(),
+            outer:   Some(outer),
+            globals: HashSet::new(),
+        }
+    }
+
+    /// Look up a variable in this scope, then in each enclosing scope.
+    pub fn get(&self, name: &str) -> Option<Value<'a>> {
+        match self.store.get(name) {
+            Some(Binding::Value(v)) => Some(v.clone()),
+            Some(Binding::Reference(place)) => Some(place.get()),
+            None => self.outer.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// Define a variable in this scope, shadowing any in enclosing scopes. If
+    /// the variable is a `byRef` parameter the value is written through to
+    /// the variable it refers to instead.
+    pub fn define(&mut self, name: &str, value: Value<'a>) {
+        match self.store.get(name) {
+            Some(Binding::Reference(place)) => place.set(value),
+            _ => {
+                self.store.insert(name.to_owned(), Binding::Value(value));
+            }
+        }
+    }
+
+    /// Define a `byRef` parameter, which refers to the place it was given
+    pub(crate) fn define_reference(&mut self, name: &str, place: Place<'a>) {
+        self.store
+            .insert(name.to_owned(), Binding::Reference(place));
+    }
+
+    /// Find where the variable is stored, looking in each enclosing scope. A
+    /// `byRef` parameter gives the place it refers to, so references never
+    /// refer to other references.
+    pub(crate) fn place_of(env: &Rc<RefCell<Self>>, name: &str) -> Option<Place<'a>> {
+        let this = env.borrow();
+        match this.store.get(name) {
+            Some(Binding::Value(_)) => Some(Place::Variable(env.clone(), name.to_owned())),
+            Some(Binding::Reference(place)) => Some(place.clone()),
+            None => Self::place_of(this.outer.as_ref()?, name),
+        }
+    }
+
+    /// Whether the variable is defined in this scope, ignoring enclosing ones.
+    pub fn contains(&self, name: &str) -> bool {
+        self.store.contains_key(name)
+    }
+
+    /// Mark a variable as global, so that assigning to it in this scope
+    /// assigns to the global variable.
+    pub fn declare_global(&mut self, name: &str) {
+        self.globals.insert(name.to_owned());
+    }
+
+    pub fn is_global(&self, name: &str) -> bool {
+        self.globals.contains(name)
+    }
+}
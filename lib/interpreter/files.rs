@@ -0,0 +1,154 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::rc::Rc;
+
+use super::RuntimeError;
+use super::Value;
+use super::ValueType;
+
+/// Where `open` and `newFile` find files, so that the host can decide whether
+/// programs get to use real files.
+pub trait FileSystem {
+    fn read(&mut self, path: &str) -> std::io::Result<String>;
+
+    /// Create the file if it doesn't exist, replacing whatever was in it
+    fn write(&mut self, path: &str, contents: &str) -> std::io::Result<()>;
+}
+impl<T: FileSystem + ?Sized> FileSystem for &mut T {
+    fn read(&mut self, path: &str) -> std::io::Result<String> {
+        (**self).read(path)
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> std::io::Result<()> {
+        (**self).write(path, contents)
+    }
+}
+
+/// The real filesystem, with paths relative to the working directory
+#[derive(Default, Debug, Clone, Copy)]
+pub struct OsFileSystem;
+impl FileSystem for OsFileSystem {
+    fn read(&mut self, path: &str) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> std::io::Result<()> {
+        std::fs::write(path, contents)
+    }
+}
+
+/// Files kept in memory, keyed by their path, so that programs can't touch
+/// anything outside of it.
+#[derive(Default, Debug, Clone)]
+pub struct MemoryFileSystem {
+    pub files: HashMap<String, String>,
+}
+impl FileSystem for MemoryFileSystem {
+    fn read(&mut self, path: &str) -> std::io::Result<String> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| ErrorKind::NotFound.into())
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> std::io::Result<()> {
+        self.files.insert(path.to_owned(), contents.to_owned());
+        Ok(())
+    }
+}
+
+/// The shared storage behind `Value::File`
+pub type File = Rc<RefCell<OpenFile>>;
+
+/// A file given by `open`. The whole file is read when it is opened, and every
+/// `writeLine` is written straight back to the filesystem.
+#[derive(Debug)]
+pub struct OpenFile {
+    pub path: String,
+    contents: String,
+    /// How far through `contents` has been read
+    pos:      usize,
+    closed:   bool,
+}
+impl OpenFile {
+    pub fn open(fs: &mut dyn FileSystem, path: &str) -> Result<Self, RuntimeError> {
+        Ok(Self {
+            path:     path.to_owned(),
+            contents: fs.read(path).map_err(|e| file_error(path, e))?,
+            pos:      0,
+            closed:   false,
+        })
+    }
+
+    fn read_line(&mut self) -> Result<String, RuntimeError> {
+        if self.end_of_file() {
+            return Err(RuntimeError::EndOfFile(self.path.clone()));
+        }
+        let rest = &self.contents[self.pos..];
+        let line = rest.split('\n').next().unwrap_or_default();
+        self.pos += (line.len() + 1).min(rest.len());
+        Ok(line.strip_suffix('\r').unwrap_or(line).to_owned())
+    }
+
+    fn write_line(&mut self, fs: &mut dyn FileSystem, line: &str) -> Result<(), RuntimeError> {
+        if !self.contents.is_empty() && !self.contents.ends_with('\n') {
+            self.contents.push('\n');
+        }
+        self.contents.push_str(line);
+        self.contents.push('\n');
+        fs.write(&self.path, &self.contents)
+            .map_err(|e| file_error(&self.path, e))
+    }
+
+    fn end_of_file(&self) -> bool {
+        self.pos >= self.contents.len()
+    }
+}
+
+/// `readLine()`, `writeLine(line)`, `endOfFile()` and `close()`
+pub fn call_method<'a>(
+    fs: &mut dyn FileSystem,
+    file: &File,
+    name: &str,
+    args: &[Value],
+) -> Result<Value<'a>, RuntimeError> {
+    let mut file = file.borrow_mut();
+    let expected = match name {
+        "readLine" | "endOfFile" | "close" => 0,
+        "writeLine" => 1,
+        _ => {
+            return Err(RuntimeError::NoSuchMember(
+                ValueType::File.to_string(),
+                name.to_owned(),
+            ))
+        }
+    };
+    if args.len() != expected {
+        return Err(RuntimeError::WrongArgumentCount {
+            func: name.to_owned(),
+            expected,
+            found: args.len(),
+        });
+    }
+    if file.closed {
+        return Err(RuntimeError::FileClosed(file.path.clone()));
+    }
+
+    match name {
+        "readLine" => Ok(Value::String(file.read_line()?)),
+        "writeLine" => {
+            file.write_line(fs, &args[0].to_string())?;
+            Ok(Value::Nothing)
+        }
+        "endOfFile" => Ok(Value::Boolean(file.end_of_file())),
+        _ => {
+            file.closed = true;
+            Ok(Value::Nothing)
+        }
+    }
+}
+
+pub fn file_error(path: &str, err: std::io::Error) -> RuntimeError {
+    RuntimeError::Io(format!("{}: {}", path, err))
+}
@@ -0,0 +1,1035 @@
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::builtins;
+use super::builtins::Builtin;
+use super::builtins::Host;
+use super::environment::Place;
+use super::files;
+use super::strings;
+use super::Array;
+use super::Class;
+use super::Environment;
+use super::FileSystem;
+use super::Function;
+use super::Io;
+use super::Object;
+use super::OsFileSystem;
+use super::Rng;
+use super::StdIo;
+use super::Value;
+use super::ValueType;
+use crate::parser::Program;
+use crate::syntax::ArrayDeclarationStatement;
+use crate::syntax::AssignStatement;
+use crate::syntax::AssignTarget;
+use crate::syntax::BlockStatement;
+use crate::syntax::ClassMethod;
+use crate::syntax::ClassStatement;
+use crate::syntax::DoUntilStatement;
+use crate::syntax::Expr;
+use crate::syntax::ForStatement;
+use crate::syntax::FunctionCallExpression;
+use crate::syntax::FunctionStatement;
+use crate::syntax::IfStatement;
+use crate::syntax::IndexExpression;
+use crate::syntax::InfixExpression;
+use crate::syntax::InfixOperator;
+use crate::syntax::MemberAccessExpression;
+use crate::syntax::MethodCallExpression;
+use crate::syntax::NewExpression;
+use crate::syntax::PassBy;
+use crate::syntax::PrefixExpression;
+use crate::syntax::PrefixOperator;
+use crate::syntax::Stmt;
+use crate::syntax::SwitchStatement;
+use crate::syntax::Visibility;
+use crate::syntax::WhileStatement;
+
+/// How deep function calls can be nested before giving up, this stops
+/// accidental infinite recursion from overflowing the real stack.
+const MAX_CALL_DEPTH: usize = 256;
+
+/// The most elements an array, counting the elements of every array inside
+/// it, can have. Bigger arrays are an error rather than running out of memory.
+const MAX_ARRAY_ELEMENTS: usize = 1 << 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuntimeError {
+    UndefinedVariable(String),
+    NotCallable(String),
+    WrongArgumentCount {
+        func:     String,
+        expected: usize,
+        found:    usize,
+    },
+    InvalidInfixOperands(InfixOperator, ValueType, ValueType),
+    InvalidPrefixOperand(PrefixOperator, ValueType),
+    NonBooleanCondition(ValueType),
+    ExpectedInteger(ValueType),
+    ExpectedNumber(ValueType),
+    /// The value, written as a string, and the type it couldn't be converted to
+    InvalidConversion(String, ValueType),
+    /// The lower bound given to `random` was more than the upper bound
+    InvalidRandomRange,
+    /// A `byRef` parameter was given something other than a variable
+    InvalidByRefArgument(String),
+    NotIndexable(ValueType),
+    IndexOutOfBounds {
+        index:  i128,
+        length: usize,
+    },
+    /// A substring which doesn't fit inside the string it was taken from
+    SubstringOutOfBounds {
+        start:  i128,
+        count:  i128,
+        length: usize,
+    },
+    InvalidArraySize(i128),
+    ArrayTooLarge,
+    NotAClass(String),
+    /// The name of the class, then the name of the member
+    NoSuchMember(String, String),
+    /// The name of the class, then the name of the member
+    PrivateMember(String, String),
+    InvalidSuper,
+    /// `input` was called after all of the input had been read
+    EndOfInput,
+    /// The path of the file
+    EndOfFile(String),
+    /// The path of the file
+    FileClosed(String),
+    Io(String),
+    ZeroLoopStep,
+    DivisionByZero,
+    IntegerOverflow,
+    ReturnOutsideFunction,
+    StackOverflow,
+
+    /// The program was parsed with error recovery and still has errors in it
+    SyntaxError,
+}
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndefinedVariable(v) => write!(f, "undefined variable '{}'", v),
+            Self::NotCallable(v) => write!(f, "'{}' is not a function or procedure", v),
+            Self::WrongArgumentCount {
+                func,
+                expected,
+                found,
+            } => write!(
+                f,
+                "'{}' takes {} argument(s) but {} were given",
+                func, expected, found
+            ),
+            Self::InvalidInfixOperands(op, l, r) => write!(
+                f,
+                "cannot apply '{}' to {} and {}",
+                op.to_string().trim(),
+                l,
+                r
+            ),
+            Self::InvalidPrefixOperand(op, v) => {
+                write!(f, "cannot apply '{}' to {}", op.to_string().trim(), v)
+            }
+            Self::NonBooleanCondition(v) => write!(f, "expected a boolean condition, found {}", v),
+            Self::ExpectedInteger(v) => write!(f, "expected an integer, found {}", v),
+            Self::ExpectedNumber(v) => write!(f, "expected a number, found {}", v),
+            Self::InvalidConversion(v, t) => write!(f, "cannot convert '{}' to {}", v, t),
+            Self::InvalidRandomRange => write!(
+                f,
+                "the lower bound given to 'random' is more than the upper bound"
+            ),
+            Self::InvalidByRefArgument(p) => write!(
+                f,
+                "the argument for '{}' is passed by reference, so it has to be a variable",
+                p
+            ),
+            Self::NotIndexable(v) => write!(f, "cannot index into {}", v),
+            Self::IndexOutOfBounds { index, length } => write!(
+                f,
+                "index {} is out of bounds for an array of length {}",
+                index, length
+            ),
+            Self::SubstringOutOfBounds {
+                start,
+                count,
+                length,
+            } => write!(
+                f,
+                "a substring of {} character(s) from position {} does not fit in a string of \
+                 length {}",
+                count, start, length
+            ),
+            Self::InvalidArraySize(s) => write!(f, "invalid array size {}", s),
+            Self::ArrayTooLarge => {
+                write!(f, "arrays can have at most {} elements", MAX_ARRAY_ELEMENTS)
+            }
+            Self::NotAClass(v) => write!(f, "'{}' is not a class", v),
+            Self::NoSuchMember(c, m) => write!(f, "'{}' has no member '{}'", c, m),
+            Self::PrivateMember(c, m) => write!(f, "'{}' is private to '{}'", m, c),
+            Self::InvalidSuper => write!(
+                f,
+                "'super' can only be used to call a method of the parent class, from inside a \
+                 method"
+            ),
+            Self::EndOfInput => write!(f, "there is no more input to read"),
+            Self::EndOfFile(p) => write!(f, "there are no more lines to read in '{}'", p),
+            Self::FileClosed(p) => write!(f, "'{}' has already been closed", p),
+            Self::Io(e) => write!(f, "input/output error: {}", e),
+            Self::ZeroLoopStep => write!(f, "the step of a for loop cannot be 0"),
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::IntegerOverflow => write!(f, "integer overflow"),
+            Self::ReturnOutsideFunction => write!(f, "return outside of a function or procedure"),
+            Self::StackOverflow => write!(f, "too many nested function calls"),
+            Self::SyntaxError => write!(f, "cannot run a statement which failed to parse"),
+        }
+    }
+}
+
+/// What executing a statement did to the flow of the program.
+enum Flow<'a> {
+    Normal(Value<'a>),
+    Return(Value<'a>),
+}
+
+/// The object whose method is running, along with the class that the method
+/// was declared in.
+#[derive(Debug, Clone)]
+struct MethodContext<'a> {
+    object: Object<'a>,
+    class:  Rc<Class<'a>>,
+}
+
+/// Tree-walking evaluator for a parsed `Program`. Variables assigned at the top
+/// level, or with `global`, live in the global environment; every function
+/// call gets a fresh environment enclosed by the one the function was declared
+/// in. Method calls are enclosed by the fields of the object instead, so that
+/// methods can use its attributes like variables and call its other methods
+/// like functions.
+pub struct Interpreter<'a> {
+    globals: Rc<RefCell<Environment<'a>>>,
+    env:     Rc<RefCell<Environment<'a>>>,
+    this:    Option<MethodContext<'a>>,
+    depth:   usize,
+    io:      Box<dyn Io + 'a>,
+    fs:      Box<dyn FileSystem + 'a>,
+    rng:     Rng,
+}
+impl Debug for Interpreter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Interpreter")
+            .field("globals", &self.globals)
+            .field("env", &self.env)
+            .field("this", &self.this)
+            .field("depth", &self.depth)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Interpreter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Interpreter<'a> {
+    /// Uses stdin and stdout for `input` and `print`, and the real filesystem
+    /// for files
+    pub fn new() -> Self {
+        Self::with_io(StdIo)
+    }
+
+    pub fn with_io(io: impl Io + 'a) -> Self {
+        Self::with_io_and_fs(io, OsFileSystem)
+    }
+
+    pub fn with_io_and_fs(io: impl Io + 'a, fs: impl FileSystem + 'a) -> Self {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        Self {
+            env: globals.clone(),
+            globals,
+            this: None,
+            depth: 0,
+            io: Box::new(io),
+            fs: Box::new(fs),
+            rng: Rng::from_entropy(),
+        }
+    }
+
+    /// Make `random` give the same numbers every time the program is run
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Run every statement in the program, giving the value of the last one.
+    pub fn run(&mut self, prog: &'a Program<'a>) -> Result<Value<'a>, RuntimeError> {
+        let mut last = Value::Nothing;
+        for stmt in &prog.statements {
+            match self.exec_statement(stmt)? {
+                Flow::Normal(v) => last = v,
+                Flow::Return(_) => return Err(RuntimeError::ReturnOutsideFunction),
+            }
+        }
+        Ok(last)
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value<'a>> {
+        self.globals.borrow().get(name)
+    }
+
+    fn exec_statement(&mut self, stmt: &'a Stmt<'a>) -> Result<Flow<'a>, RuntimeError> {
+        match stmt {
+            Stmt::Assign(a) => self.exec_assign(a),
+            Stmt::ArrayDeclaration(a) => self.exec_array_declaration(a),
+            Stmt::Return(r) => Ok(Flow::Return(match &r.value {
+                Some(v) => self.eval_expr(v)?,
+                None => Value::Nothing,
+            })),
+            Stmt::Expression(e) => Ok(Flow::Normal(self.eval_expr(&e.value)?)),
+            Stmt::If(i) => self.exec_if(i),
+            Stmt::For(f) => self.exec_for(f),
+            Stmt::While(w) => self.exec_while(w),
+            Stmt::DoUntil(d) => self.exec_do_until(d),
+            Stmt::Switch(s) => self.exec_switch(s),
+            Stmt::Block(b) => self.exec_block(b),
+            Stmt::Function(f) => {
+                let func = Value::Function(Function {
+                    decl: f,
+                    env:  self.env.clone(),
+                });
+                self.env.borrow_mut().define(f.ident.get_ident(), func);
+                Ok(Flow::Normal(Value::Nothing))
+            }
+            Stmt::Class(c) => self.exec_class(c),
+            Stmt::Error(_) => Err(RuntimeError::SyntaxError),
+            Stmt::Empty => Ok(Flow::Normal(Value::Nothing)),
+        }
+    }
+
+    fn exec_assign(&mut self, stmt: &'a AssignStatement<'a>) -> Result<Flow<'a>, RuntimeError> {
+        let value = self.eval_expr(&stmt.value)?;
+        match &stmt.target {
+            AssignTarget::Identifier(i) if stmt.global => {
+                self.env.borrow_mut().declare_global(i.get_ident());
+                self.globals.borrow_mut().define(i.get_ident(), value)
+            }
+            AssignTarget::Identifier(i) => self.assign_variable(i.get_ident(), value),
+            AssignTarget::Index(i) => self.assign_element(i, value)?,
+            AssignTarget::Member(m) => self.assign_attribute(m, value)?,
+        }
+        Ok(Flow::Normal(Value::Nothing))
+    }
+
+    /// Variables in a function or method are local to it unless they were
+    /// declared `global`, so assigning never changes a variable of an enclosing
+    /// scope. The exception is the attributes of the object a method was called
+    /// on, which can be assigned to by name.
+    fn assign_variable(&mut self, name: &str, value: Value<'a>) {
+        if self.env.borrow().contains(name) {
+            self.env.borrow_mut().define(name, value);
+        } else if self.env.borrow().is_global(name) {
+            self.globals.borrow_mut().define(name, value);
+        } else {
+            let env = match &self.this {
+                Some(this) if this.object.fields.borrow().contains(name) => &this.object.fields,
+                _ => &self.env,
+            };
+            env.borrow_mut().define(name, value);
+        }
+    }
+
+    fn assign_element(
+        &mut self,
+        target: &'a IndexExpression<'a>,
+        value: Value<'a>,
+    ) -> Result<(), RuntimeError> {
+        let (array, index) = self.eval_element(target)?;
+        array.borrow_mut()[index] = value;
+        Ok(())
+    }
+
+    fn assign_attribute(
+        &mut self,
+        target: &'a MemberAccessExpression<'a>,
+        value: Value<'a>,
+    ) -> Result<(), RuntimeError> {
+        let name = target.member.get_ident();
+        let object = self.eval_object(&target.subject, name)?;
+        self.check_attribute(&object, name)?;
+        object.fields.borrow_mut().define(name, value);
+        Ok(())
+    }
+
+    fn exec_class(&mut self, stmt: &'a ClassStatement<'a>) -> Result<Flow<'a>, RuntimeError> {
+        let parent = match &stmt.parent {
+            Some(p) => Some(self.lookup_class(p.get_ident())?),
+            None => None,
+        };
+        let class = Value::Class(Rc::new(Class {
+            decl: stmt,
+            parent,
+            env: self.env.clone(),
+        }));
+        self.env.borrow_mut().define(stmt.ident.get_ident(), class);
+        Ok(Flow::Normal(Value::Nothing))
+    }
+
+    /// Every element starts out as `Nothing`. The array is always defined in
+    /// the current scope, like a parameter.
+    fn exec_array_declaration(
+        &mut self,
+        stmt: &'a ArrayDeclarationStatement<'a>,
+    ) -> Result<Flow<'a>, RuntimeError> {
+        let mut sizes = Vec::with_capacity(stmt.sizes.len());
+        let mut elements: usize = 1;
+        for size in &stmt.sizes {
+            let size = self.eval_integer(size)?;
+            let size = usize::try_from(size).map_err(|_| RuntimeError::InvalidArraySize(size))?;
+            elements = elements
+                .checked_mul(size)
+                .filter(|e| *e <= MAX_ARRAY_ELEMENTS)
+                .ok_or(RuntimeError::ArrayTooLarge)?;
+            sizes.push(size);
+        }
+        self.env
+            .borrow_mut()
+            .define(stmt.ident.get_ident(), new_array(&sizes));
+        Ok(Flow::Normal(Value::Nothing))
+    }
+
+    fn exec_block(&mut self, block: &'a BlockStatement<'a>) -> Result<Flow<'a>, RuntimeError> {
+        let mut last = Value::Nothing;
+        for stmt in &block.statements {
+            match self.exec_statement(stmt)? {
+                Flow::Normal(v) => last = v,
+                ret @ Flow::Return(_) => return Ok(ret),
+            }
+        }
+        Ok(Flow::Normal(last))
+    }
+
+    fn exec_if(&mut self, stmt: &'a IfStatement<'a>) -> Result<Flow<'a>, RuntimeError> {
+        if self.eval_condition(&stmt.condition)? {
+            return self.exec_block(&stmt.consequence);
+        }
+        for (condition, block) in &stmt.elseifs {
+            if self.eval_condition(condition)? {
+                return self.exec_block(block);
+            }
+        }
+        match &stmt.alternative {
+            Some(alt) => self.exec_block(alt),
+            None => Ok(Flow::Normal(Value::Nothing)),
+        }
+    }
+
+    /// The bounds and step are only evaluated once, before the first iteration.
+    /// The loop is inclusive of the end bound, counting down if the step is
+    /// negative.
+    fn exec_for(&mut self, stmt: &'a ForStatement<'a>) -> Result<Flow<'a>, RuntimeError> {
+        let start = self.eval_integer(&stmt.start)?;
+        let end = self.eval_integer(&stmt.end)?;
+        let step = match &stmt.step {
+            Some(s) => self.eval_integer(s)?,
+            None => 1,
+        };
+        if step == 0 {
+            return Err(RuntimeError::ZeroLoopStep);
+        }
+
+        let mut i = start;
+        while (step > 0 && i <= end) || (step < 0 && i >= end) {
+            self.assign_variable(stmt.counter.get_ident(), Value::Integer(i));
+            if let ret @ Flow::Return(_) = self.exec_block(&stmt.body)? {
+                return Ok(ret);
+            }
+            i = match i.checked_add(step) {
+                Some(i) => i,
+                None => break,
+            };
+        }
+        Ok(Flow::Normal(Value::Nothing))
+    }
+
+    fn exec_while(&mut self, stmt: &'a WhileStatement<'a>) -> Result<Flow<'a>, RuntimeError> {
+        while self.eval_condition(&stmt.condition)? {
+            if let ret @ Flow::Return(_) = self.exec_block(&stmt.body)? {
+                return Ok(ret);
+            }
+        }
+        Ok(Flow::Normal(Value::Nothing))
+    }
+
+    fn exec_do_until(&mut self, stmt: &'a DoUntilStatement<'a>) -> Result<Flow<'a>, RuntimeError> {
+        loop {
+            if let ret @ Flow::Return(_) = self.exec_block(&stmt.body)? {
+                return Ok(ret);
+            }
+            if self.eval_condition(&stmt.condition)? {
+                return Ok(Flow::Normal(Value::Nothing));
+            }
+        }
+    }
+
+    /// Cases are compared in order with `==`, a case which `==` can't compare
+    /// to the subject never matches. There is no fall-through between cases.
+    fn exec_switch(&mut self, stmt: &'a SwitchStatement<'a>) -> Result<Flow<'a>, RuntimeError> {
+        let subject = self.eval_expr(&stmt.subject)?;
+        for case in &stmt.cases {
+            let value = self.eval_expr(&case.value)?;
+            let matched = eval_infix_values(&InfixOperator::DoubleEquals, &subject, &value);
+            if matches!(matched, Ok(Value::Boolean(true))) {
+                return self.exec_block(&case.body);
+            }
+        }
+        match &stmt.default {
+            Some(d) => self.exec_block(d),
+            None => Ok(Flow::Normal(Value::Nothing)),
+        }
+    }
+
+    fn eval_integer(&mut self, expr: &'a Expr<'a>) -> Result<i128, RuntimeError> {
+        match self.eval_expr(expr)? {
+            Value::Integer(i) => Ok(i),
+            v => Err(RuntimeError::ExpectedInteger((&v).into())),
+        }
+    }
+
+    fn eval_condition(&mut self, expr: &'a Expr<'a>) -> Result<bool, RuntimeError> {
+        match self.eval_expr(expr)? {
+            Value::Boolean(b) => Ok(b),
+            v => Err(RuntimeError::NonBooleanCondition((&v).into())),
+        }
+    }
+
+    fn eval_expr(&mut self, expr: &'a Expr<'a>) -> Result<Value<'a>, RuntimeError> {
+        match expr {
+            Expr::Identifier(i) => self
+                .env
+                .borrow()
+                .get(i.get_ident())
+                .ok_or_else(|| RuntimeError::UndefinedVariable(i.get_ident().to_owned())),
+            Expr::Boolean(b) => Ok(Value::Boolean(b.value)),
+            Expr::IntegerLiteral(i) => Ok(Value::Integer(i.value)),
+            Expr::RealLiteral(r) => Ok(Value::Real(r.value)),
+            Expr::StringLiteral(s) => Ok(Value::String(s.value.to_owned())),
+            Expr::Placeholder(_) => Ok(Value::Nothing),
+            Expr::Prefix(p) => self.eval_prefix(p),
+            Expr::Infix(i) => self.eval_infix(i),
+            Expr::FunctionCall(c) => self.call_function(c),
+            Expr::Index(i) => {
+                let (array, index) = self.eval_element(i)?;
+                let element = array.borrow()[index].clone();
+                Ok(element)
+            }
+            Expr::New(n) => self.eval_new(n),
+            Expr::Super(_) => Err(RuntimeError::InvalidSuper),
+            Expr::MemberAccess(m) => self.eval_member_access(m),
+            Expr::MethodCall(m) => self.call_method(m),
+        }
+    }
+
+    fn lookup_class(&self, name: &str) -> Result<Rc<Class<'a>>, RuntimeError> {
+        match self.env.borrow().get(name) {
+            Some(Value::Class(c)) => Ok(c),
+            Some(_) => Err(RuntimeError::NotAClass(name.to_owned())),
+            None => Err(RuntimeError::UndefinedVariable(name.to_owned())),
+        }
+    }
+
+    /// Every attribute starts out as `Nothing`, then the constructor is run if
+    /// the class or one of its parents has one.
+    fn eval_new(&mut self, expr: &'a NewExpression<'a>) -> Result<Value<'a>, RuntimeError> {
+        let class = self.lookup_class(expr.class.get_ident())?;
+        let mut fields = Environment::new_enclosed(class.env.clone());
+        let mut next = Some(&class);
+        while let Some(c) = next {
+            for attr in &c.decl.attributes {
+                fields.define(attr.ident.get_ident(), Value::Nothing);
+            }
+            next = c.parent.as_ref();
+        }
+        let object = Object {
+            class:  class.clone(),
+            fields: Rc::new(RefCell::new(fields)),
+        };
+
+        match class.find_method("new") {
+            Some((method, owner)) => {
+                let this = MethodContext {
+                    object: object.clone(),
+                    class:  owner,
+                };
+                self.invoke(&method.func, object.fields.clone(), &expr.args, Some(this))?;
+            }
+            None if expr.args.is_empty() => (),
+            None => {
+                return Err(RuntimeError::WrongArgumentCount {
+                    func:     "new".to_owned(),
+                    expected: 0,
+                    found:    expr.args.len(),
+                })
+            }
+        }
+        Ok(Value::Object(object))
+    }
+
+    /// As well as the attributes of objects, strings have `length`, `upper`
+    /// and `lower`, and arrays have `length`.
+    fn eval_member_access(
+        &mut self,
+        expr: &'a MemberAccessExpression<'a>,
+    ) -> Result<Value<'a>, RuntimeError> {
+        let name = expr.member.get_ident();
+        match self.eval_expr(&expr.subject)? {
+            Value::Object(object) => {
+                self.check_attribute(&object, name)?;
+                let value = object.fields.borrow().get(name).unwrap_or(Value::Nothing);
+                Ok(value)
+            }
+            Value::String(s) => strings::property(&s, name),
+            Value::Array(a) if name == "length" => Ok(Value::Integer(a.borrow().len() as i128)),
+            v => Err(RuntimeError::NoSuchMember(
+                ValueType::from(&v).to_string(),
+                name.to_owned(),
+            )),
+        }
+    }
+
+    /// Evaluate the subject of an assignment to an attribute, which has to be
+    /// an object
+    fn eval_object(
+        &mut self,
+        expr: &'a Expr<'a>,
+        member: &str,
+    ) -> Result<Object<'a>, RuntimeError> {
+        match self.eval_expr(expr)? {
+            Value::Object(o) => Ok(o),
+            v => Err(RuntimeError::NoSuchMember(
+                ValueType::from(&v).to_string(),
+                member.to_owned(),
+            )),
+        }
+    }
+
+    /// Check that the object has the attribute and that it can be used from
+    /// here
+    fn check_attribute(&self, object: &Object<'a>, name: &str) -> Result<(), RuntimeError> {
+        match object.class.find_attribute(name) {
+            Some((attr, owner)) => self.check_visibility(object, attr.visibility, owner, name),
+            None => Err(RuntimeError::NoSuchMember(
+                object.class.name().to_owned(),
+                name.to_owned(),
+            )),
+        }
+    }
+
+    /// Private members can only be used from inside the methods of a class that
+    /// the object is an instance of. `owner` is the class which declares the
+    /// member.
+    fn check_visibility(
+        &self,
+        object: &Object<'a>,
+        visibility: Visibility,
+        owner: &Class<'a>,
+        name: &str,
+    ) -> Result<(), RuntimeError> {
+        let inside = self
+            .this
+            .as_ref()
+            .is_some_and(|this| object.class.is_subclass_of(&this.class));
+        if visibility == Visibility::Private && !inside {
+            return Err(RuntimeError::PrivateMember(
+                owner.name().to_owned(),
+                name.to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Find the array that an index expression refers into, along with the
+    /// position of the element in it. For `board[x, y]` that is `board[x]` and
+    /// `y`.
+    fn eval_element(
+        &mut self,
+        expr: &'a IndexExpression<'a>,
+    ) -> Result<(Array<'a>, usize), RuntimeError> {
+        let mut array = match self.eval_expr(&expr.subject)? {
+            Value::Array(a) => a,
+            v => return Err(RuntimeError::NotIndexable((&v).into())),
+        };
+        let (last, rest) = expr
+            .indices
+            .split_last()
+            .expect("an index expression always has an index");
+        for index in rest {
+            let index = self.eval_index(index, array.borrow().len())?;
+            let element = array.borrow()[index].clone();
+            array = match element {
+                Value::Array(a) => a,
+                v => return Err(RuntimeError::NotIndexable((&v).into())),
+            };
+        }
+        let index = self.eval_index(last, array.borrow().len())?;
+        Ok((array, index))
+    }
+
+    fn eval_index(&mut self, expr: &'a Expr<'a>, length: usize) -> Result<usize, RuntimeError> {
+        let index = self.eval_integer(expr)?;
+        match usize::try_from(index) {
+            Ok(i) if i < length => Ok(i),
+            _ => Err(RuntimeError::IndexOutOfBounds { index, length }),
+        }
+    }
+
+    fn eval_prefix(&mut self, expr: &'a PrefixExpression<'a>) -> Result<Value<'a>, RuntimeError> {
+        let subject = self.eval_expr(&expr.subject)?;
+        match (&expr.operator, subject) {
+            (PrefixOperator::Plus, Value::Integer(i)) => Ok(Value::Integer(i)),
+            (PrefixOperator::Minus, Value::Integer(i)) => i
+                .checked_neg()
+                .map(Value::Integer)
+                .ok_or(RuntimeError::IntegerOverflow),
+            (PrefixOperator::Plus, Value::Real(r)) => Ok(Value::Real(r)),
+            (PrefixOperator::Minus, Value::Real(r)) => Ok(Value::Real(-r)),
+            (PrefixOperator::Not, Value::Boolean(b)) => Ok(Value::Boolean(!b)),
+            (op, v) => Err(RuntimeError::InvalidPrefixOperand(op.clone(), (&v).into())),
+        }
+    }
+
+    /// `+` on two strings joins them together. Anything else has to be
+    /// converted to a string before it can be joined onto one, `"a" + 1` is an
+    /// error.
+    ///
+    /// When an integer meets a real the integer is converted to a real first.
+    /// `/` is always real division, even on two integers, while `DIV` and `MOD`
+    /// only work on integers. `^` gives an integer when an integer is raised to
+    /// a positive integer power and a real otherwise.
+    fn eval_infix(&mut self, expr: &'a InfixExpression<'a>) -> Result<Value<'a>, RuntimeError> {
+        let left = self.eval_expr(&expr.left)?;
+
+        // AND and OR short circuit, so the right hand side might never be evaluated
+        match (&expr.operator, &left) {
+            (InfixOperator::And, Value::Boolean(false)) => return Ok(Value::Boolean(false)),
+            (InfixOperator::Or, Value::Boolean(true)) => return Ok(Value::Boolean(true)),
+            _ => (),
+        }
+
+        let right = self.eval_expr(&expr.right)?;
+        eval_infix_values(&expr.operator, &left, &right)
+    }
+
+    fn call_function(
+        &mut self,
+        call: &'a FunctionCallExpression<'a>,
+    ) -> Result<Value<'a>, RuntimeError> {
+        let name = call.func.get_ident();
+        // Inside a method, the other methods of the object can be called by name
+        let method = self
+            .this
+            .as_ref()
+            .and_then(|this| Some((this.object.clone(), this.object.class.find_method(name)?)));
+        if let Some((object, (method, owner))) = method {
+            return self.invoke_method(object, method, owner, &call.args);
+        }
+
+        let value = self.env.borrow().get(name);
+        let func = match value {
+            Some(Value::Function(f)) => f,
+            Some(_) => return Err(RuntimeError::NotCallable(name.to_owned())),
+            None => match builtins::lookup(name) {
+                Some(builtin) => return self.call_builtin(builtin, &call.args),
+                None => return Err(RuntimeError::UndefinedVariable(name.to_owned())),
+            },
+        };
+        self.invoke(func.decl, func.env, &call.args, None)
+    }
+
+    fn eval_args(&mut self, args: &'a [Expr<'a>]) -> Result<Vec<Value<'a>>, RuntimeError> {
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            values.push(self.eval_expr(arg)?);
+        }
+        Ok(values)
+    }
+
+    fn call_builtin(
+        &mut self,
+        builtin: Builtin,
+        args: &'a [Expr<'a>],
+    ) -> Result<Value<'a>, RuntimeError> {
+        let values = self.eval_args(args)?;
+        let mut host = Host {
+            io:  &mut *self.io,
+            fs:  &mut *self.fs,
+            rng: &mut self.rng,
+        };
+        builtin(&mut host, values)
+    }
+
+    /// `super.method()` looks for the method starting from the parent of the
+    /// class that the running method was declared in, and calls it on the same
+    /// object.
+    fn call_method(
+        &mut self,
+        call: &'a MethodCallExpression<'a>,
+    ) -> Result<Value<'a>, RuntimeError> {
+        let name = call.method.get_ident();
+        let (object, class) = match *call.subject {
+            Expr::Super(_) => {
+                let this = self.this.clone().ok_or(RuntimeError::InvalidSuper)?;
+                let parent = this
+                    .class
+                    .parent
+                    .clone()
+                    .ok_or(RuntimeError::InvalidSuper)?;
+                (this.object, parent)
+            }
+            _ => match self.eval_expr(&call.subject)? {
+                Value::Object(object) => {
+                    let class = object.class.clone();
+                    (object, class)
+                }
+                Value::String(s) => {
+                    let args = self.eval_args(&call.args)?;
+                    return strings::call_method(&s, name, &args);
+                }
+                Value::File(f) => {
+                    let args = self.eval_args(&call.args)?;
+                    return files::call_method(&mut *self.fs, &f, name, &args);
+                }
+                v => {
+                    return Err(RuntimeError::NoSuchMember(
+                        ValueType::from(&v).to_string(),
+                        name.to_owned(),
+                    ))
+                }
+            },
+        };
+        let (method, owner) = class
+            .find_method(name)
+            .ok_or_else(|| RuntimeError::NoSuchMember(class.name().to_owned(), name.to_owned()))?;
+        self.check_visibility(&object, method.visibility, &owner, name)?;
+        self.invoke_method(object, method, owner, &call.args)
+    }
+
+    /// Run a method on the object, `owner` being the class the method was
+    /// found in
+    fn invoke_method(
+        &mut self,
+        object: Object<'a>,
+        method: &'a ClassMethod<'a>,
+        owner: Rc<Class<'a>>,
+        args: &'a [Expr<'a>],
+    ) -> Result<Value<'a>, RuntimeError> {
+        let fields = object.fields.clone();
+        let this = MethodContext {
+            object,
+            class: owner,
+        };
+        self.invoke(&method.func, fields, args, Some(this))
+    }
+
+    /// Find the variable, element or attribute that a `byRef` argument refers
+    /// to. The argument is only evaluated once, so an index like `a[next()]`
+    /// calls `next` once.
+    fn eval_place(&mut self, arg: &'a Expr<'a>, param: &str) -> Result<Place<'a>, RuntimeError> {
+        match arg {
+            Expr::Identifier(i) => Environment::place_of(&self.env, i.get_ident())
+                .ok_or_else(|| RuntimeError::UndefinedVariable(i.get_ident().to_owned())),
+            Expr::Index(i) => {
+                let (array, index) = self.eval_element(i)?;
+                Ok(Place::Element(array, index))
+            }
+            Expr::MemberAccess(m) => {
+                let name = m.member.get_ident();
+                let object = self.eval_object(&m.subject, name)?;
+                self.check_attribute(&object, name)?;
+                Ok(Place::Variable(object.fields, name.to_owned()))
+            }
+            _ => Err(RuntimeError::InvalidByRefArgument(param.to_owned())),
+        }
+    }
+
+    /// Run a function or method with the arguments bound to its parameters, in
+    /// a new environment enclosed by `outer`. The arguments are evaluated in
+    /// the caller's environment.
+    fn invoke(
+        &mut self,
+        decl: &'a FunctionStatement<'a>,
+        outer: Rc<RefCell<Environment<'a>>>,
+        args: &'a [Expr<'a>],
+        this: Option<MethodContext<'a>>,
+    ) -> Result<Value<'a>, RuntimeError> {
+        if decl.params.len() != args.len() {
+            return Err(RuntimeError::WrongArgumentCount {
+                func:     decl.ident.get_ident().to_owned(),
+                expected: decl.params.len(),
+                found:    args.len(),
+            });
+        }
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::StackOverflow);
+        }
+
+        let mut env = Environment::new_enclosed(outer);
+        for (param, arg) in decl.params.iter().zip(args) {
+            let name = param.ident.get_ident();
+            match param.pass_by {
+                Some(PassBy::Reference) => {
+                    let place = self.eval_place(arg, name)?;
+                    env.define_reference(name, place);
+                }
+                Some(PassBy::Value) | None => env.define(name, deep_copy(self.eval_expr(arg)?)),
+            }
+        }
+
+        let env = Rc::new(RefCell::new(env));
+        let caller_env = std::mem::replace(&mut self.env, env);
+        let caller_this = std::mem::replace(&mut self.this, this);
+        self.depth += 1;
+        let result = self.exec_block(&decl.body);
+        self.depth -= 1;
+        self.env = caller_env;
+        self.this = caller_this;
+
+        match result? {
+            Flow::Return(v) => Ok(v),
+            Flow::Normal(_) => Ok(Value::Nothing),
+        }
+    }
+}
+
+/// A copy of a value which shares nothing with the original, so that arrays
+/// passed by value can be changed without affecting the caller. Objects are
+/// still shared.
+fn deep_copy(value: Value) -> Value {
+    match value {
+        Value::Array(a) => Value::Array(Rc::new(RefCell::new(
+            a.borrow().iter().cloned().map(deep_copy).collect(),
+        ))),
+        v => v,
+    }
+}
+
+/// An array with the first size, each element of which is a separate array
+/// with the rest of the sizes.
+fn new_array<'a>(sizes: &[usize]) -> Value<'a> {
+    match sizes.split_first() {
+        Some((&size, rest)) => Value::Array(Rc::new(RefCell::new(
+            (0..size).map(|_| new_array(rest)).collect(),
+        ))),
+        None => Value::Nothing,
+    }
+}
+
+/// Apply an infix operator to two values which have already been evaluated,
+/// see `eval_infix`
+fn eval_infix_values<'a>(
+    op: &InfixOperator,
+    left: &Value<'a>,
+    right: &Value<'a>,
+) -> Result<Value<'a>, RuntimeError> {
+    match (op, left, right) {
+        (op, Value::Integer(l), Value::Integer(r)) => eval_integer_infix(op, *l, *r),
+        (op, Value::Real(l), Value::Real(r)) => eval_real_infix(op, *l, *r),
+        (op, Value::Integer(l), Value::Real(r)) => eval_real_infix(op, *l as f64, *r),
+        (op, Value::Real(l), Value::Integer(r)) => eval_real_infix(op, *l, *r as f64),
+        (InfixOperator::Plus, Value::String(l), Value::String(r)) => {
+            Ok(Value::String(l.to_owned() + r))
+        }
+        (InfixOperator::And | InfixOperator::Or, Value::Boolean(_), Value::Boolean(r)) => {
+            Ok(Value::Boolean(*r))
+        }
+        (InfixOperator::DoubleEquals, l, r) if ValueType::from(l) == r.into() => {
+            Ok(Value::Boolean(l == r))
+        }
+        (InfixOperator::NotEqual, l, r) if ValueType::from(l) == r.into() => {
+            Ok(Value::Boolean(l != r))
+        }
+        (op, l, r) => Err(RuntimeError::InvalidInfixOperands(
+            op.clone(),
+            l.into(),
+            r.into(),
+        )),
+    }
+}
+
+/// `DIV` rounds towards negative infinity, and `MOD` takes the sign of the
+/// divisor, so that `a = (a DIV b) * b + (a MOD b)` always holds.
+fn eval_integer_infix<'a>(op: &InfixOperator, l: i128, r: i128) -> Result<Value<'a>, RuntimeError> {
+    use InfixOperator::*;
+
+    let int = |v: Option<i128>| v.map(Value::Integer).ok_or(RuntimeError::IntegerOverflow);
+    match op {
+        Plus => int(l.checked_add(r)),
+        Minus => int(l.checked_sub(r)),
+        Multiply => int(l.checked_mul(r)),
+        // The power of -1, 0 or 1 stays in range however big the exponent is,
+        // even beyond what `checked_pow` takes
+        Power if r > 0 && (-1..=1).contains(&l) => {
+            Ok(Value::Integer(if l == -1 && r % 2 == 0 { 1 } else { l }))
+        }
+        Power if r >= 0 => int(u32::try_from(r).ok().and_then(|r| l.checked_pow(r))),
+        Power => eval_real_infix(op, l as f64, r as f64),
+        Divide | Div | Mod if r == 0 => Err(RuntimeError::DivisionByZero),
+        Divide => Ok(Value::Real(l as f64 / r as f64)),
+        Div => {
+            let q = l.checked_div(r).ok_or(RuntimeError::IntegerOverflow)?;
+            if l % r != 0 && (l < 0) != (r < 0) {
+                Ok(Value::Integer(q - 1))
+            } else {
+                Ok(Value::Integer(q))
+            }
+        }
+        Mod => {
+            let m = l.checked_rem(r).ok_or(RuntimeError::IntegerOverflow)?;
+            if m != 0 && (m < 0) != (r < 0) {
+                Ok(Value::Integer(m + r))
+            } else {
+                Ok(Value::Integer(m))
+            }
+        }
+        DoubleEquals => Ok(Value::Boolean(l == r)),
+        NotEqual => Ok(Value::Boolean(l != r)),
+        LThan => Ok(Value::Boolean(l < r)),
+        LThanOrEqual => Ok(Value::Boolean(l <= r)),
+        GThan => Ok(Value::Boolean(l > r)),
+        GThanOrEqual => Ok(Value::Boolean(l >= r)),
+        And | Or | LParenthasis => Err(RuntimeError::InvalidInfixOperands(
+            op.clone(),
+            ValueType::Integer,
+            ValueType::Integer,
+        )),
+    }
+}
+
+fn eval_real_infix<'a>(op: &InfixOperator, l: f64, r: f64) -> Result<Value<'a>, RuntimeError> {
+    use InfixOperator::*;
+
+    match op {
+        Plus => Ok(Value::Real(l + r)),
+        Minus => Ok(Value::Real(l - r)),
+        Multiply => Ok(Value::Real(l * r)),
+        Power if l == 0.0 && r < 0.0 => Err(RuntimeError::DivisionByZero),
+        Power => Ok(Value::Real(l.powf(r))),
+        Divide if r == 0.0 => Err(RuntimeError::DivisionByZero),
+        Divide => Ok(Value::Real(l / r)),
+        Div | Mod => Err(RuntimeError::ExpectedInteger(ValueType::Real)),
+        DoubleEquals => Ok(Value::Boolean(l == r)),
+        NotEqual => Ok(Value::Boolean(l != r)),
+        LThan => Ok(Value::Boolean(l < r)),
+        LThanOrEqual => Ok(Value::Boolean(l <= r)),
+        GThan => Ok(Value::Boolean(l > r)),
+        GThanOrEqual => Ok(Value::Boolean(l >= r)),
+        And | Or | LParenthasis => Err(RuntimeError::InvalidInfixOperands(
+            op.clone(),
+            ValueType::Real,
+            ValueType::Real,
+        )),
+    }
+}
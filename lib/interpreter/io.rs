@@ -0,0 +1,88 @@
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::io::Write;
+
+/// Where `print` writes to and `input` reads from, so that the host can decide
+/// what a program's input and output are connected to.
+pub trait Io {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()>;
+
+    /// Show the prompt and read a line, without its line ending. Gives `None`
+    /// once there is no more input.
+    fn read_line(&mut self, prompt: &str) -> std::io::Result<Option<String>>;
+}
+impl<T: Io + ?Sized> Io for &mut T {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        (**self).write_line(line)
+    }
+
+    fn read_line(&mut self, prompt: &str) -> std::io::Result<Option<String>> {
+        (**self).read_line(prompt)
+    }
+}
+
+/// Reads from stdin and writes to stdout
+#[derive(Default, Debug, Clone, Copy)]
+pub struct StdIo;
+impl Io for StdIo {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        writeln!(std::io::stdout().lock(), "{}", line)
+    }
+
+    fn read_line(&mut self, prompt: &str) -> std::io::Result<Option<String>> {
+        let mut stdout = std::io::stdout().lock();
+        write!(stdout, "{}", prompt)?;
+        stdout.flush()?;
+
+        let mut line = String::new();
+        if std::io::stdin().lock().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+}
+
+/// Takes its input from a list of lines given up front and collects the
+/// output in memory, for tests and for marking programs automatically. Each
+/// line that is read is written to the output after its prompt, so `output`
+/// looks like what would have been on the terminal.
+#[derive(Default, Debug, Clone)]
+pub struct BufferIo {
+    pub input:  VecDeque<String>,
+    pub output: String,
+}
+impl BufferIo {
+    pub fn new<I, S>(input: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            input:  input.into_iter().map(Into::into).collect(),
+            output: String::new(),
+        }
+    }
+}
+impl Io for BufferIo {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        self.output.push_str(line);
+        self.output.push('\n');
+        Ok(())
+    }
+
+    fn read_line(&mut self, prompt: &str) -> std::io::Result<Option<String>> {
+        self.output.push_str(prompt);
+        let line = self.input.pop_front();
+        if let Some(l) = &line {
+            self.output.push_str(l);
+            self.output.push('\n');
+        }
+        Ok(line)
+    }
+}
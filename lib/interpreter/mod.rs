@@ -0,0 +1,31 @@
+mod builtins;
+mod environment;
+mod files;
+#[allow(clippy::module_inception)]
+mod interpreter;
+mod io;
+mod random;
+mod strings;
+mod value;
+
+#[cfg(test)]
+mod test;
+
+pub use environment::Environment;
+pub use files::File;
+pub use files::FileSystem;
+pub use files::MemoryFileSystem;
+pub use files::OpenFile;
+pub use files::OsFileSystem;
+pub use interpreter::Interpreter;
+pub use interpreter::RuntimeError;
+pub use io::BufferIo;
+pub use io::Io;
+pub use io::StdIo;
+pub use random::Rng;
+pub use value::Array;
+pub use value::Class;
+pub use value::Function;
+pub use value::Object;
+pub use value::Value;
+pub use value::ValueType;
@@ -0,0 +1,36 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+/// A small SplitMix64 generator behind `random`. It is nowhere near good
+/// enough for cryptography, but the same seed always gives the same numbers on
+/// every platform, which is what matters for marking programs.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seeded differently every time
+    pub fn from_entropy() -> Self {
+        // Every `RandomState` has different keys, so hashing nothing with a new
+        // one gives a new random number
+        Self::new(RandomState::new().hash_one(()))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A real in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill the mantissa
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,70 @@
+use super::RuntimeError;
+use super::Value;
+use super::ValueType;
+
+/// The members of a string which are used without brackets, like
+/// `name.length`
+pub fn property<'a>(s: &str, name: &str) -> Result<Value<'a>, RuntimeError> {
+    match name {
+        "length" => Ok(Value::Integer(s.chars().count() as i128)),
+        "upper" => Ok(Value::String(s.to_uppercase())),
+        "lower" => Ok(Value::String(s.to_lowercase())),
+        _ => Err(no_such_member(name)),
+    }
+}
+
+/// `s.substring(start, count)`, `s.left(count)` and `s.right(count)`. Positions
+/// count characters from 0, and the substring has to fit inside the string.
+pub fn call_method<'a>(s: &str, name: &str, args: &[Value]) -> Result<Value<'a>, RuntimeError> {
+    let length = s.chars().count() as i128;
+    let out_of_bounds = |start, count| RuntimeError::SubstringOutOfBounds {
+        start,
+        count,
+        length: length as usize,
+    };
+    let (start, count) = match (name, args) {
+        ("substring", [start, count]) => (integer(start)?, integer(count)?),
+        ("left", [count]) => (0, integer(count)?),
+        ("right", [count]) => {
+            let count = integer(count)?;
+            // A count too far below zero for there to be a start is out of
+            // bounds however it is reported
+            let start = length
+                .checked_sub(count)
+                .ok_or_else(|| out_of_bounds(length, count))?;
+            (start, count)
+        }
+        ("substring" | "left" | "right", _) => {
+            return Err(RuntimeError::WrongArgumentCount {
+                func:     name.to_owned(),
+                expected: if name == "substring" { 2 } else { 1 },
+                found:    args.len(),
+            })
+        }
+        _ => return Err(no_such_member(name)),
+    };
+
+    let fits = start
+        .checked_add(count)
+        .is_some_and(|end| count >= 0 && start >= 0 && end <= length);
+    if !fits {
+        return Err(out_of_bounds(start, count));
+    }
+    Ok(Value::String(
+        s.chars()
+            .skip(start as usize)
+            .take(count as usize)
+            .collect(),
+    ))
+}
+
+fn integer(value: &Value) -> Result<i128, RuntimeError> {
+    match value {
+        Value::Integer(i) => Ok(*i),
+        v => Err(RuntimeError::ExpectedInteger(v.into())),
+    }
+}
+
+fn no_such_member(name: &str) -> RuntimeError {
+    RuntimeError::NoSuchMember(ValueType::String.to_string(), name.to_owned())
+}
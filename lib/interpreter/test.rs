@@ -0,0 +1,939 @@
+use super::BufferIo;
+use super::Interpreter;
+use super::MemoryFileSystem;
+use super::RuntimeError;
+use super::Value;
+use super::ValueType;
+use crate::parser::parse_from_string;
+use crate::syntax::InfixOperator;
+
+/// Run the program, giving the value of the last statement as a string
+fn eval(input: &str) -> Result<String, RuntimeError> {
+    let prog = parse_from_string(input).unwrap();
+    let result = Interpreter::new().run(&prog).map(|v| v.to_string());
+    result
+}
+
+#[test]
+fn test_eval_integer_arithmetic() {
+    let input = [
+        ["5", "5"],
+        ["-5", "-5"],
+        ["1 + 2 * 3", "7"],
+        ["(1 + 2) * 3", "9"],
+        ["10 - 4 - 3", "3"],
+        ["7 DIV 2", "3"],
+        ["-7 DIV 2", "-4"],
+        ["7 MOD 3", "1"],
+        ["-7 MOD 3", "2"],
+        ["7 MOD -3", "-2"],
+        ["2 ^ 10", "1024"],
+        ["2 ^ 3 ^ 2", "512"],
+        ["-2 ^ 2", "-4"],
+        ["(-2) ^ 3", "-8"],
+        ["5 ^ 0", "1"],
+        ["1 ^ 5000000000", "1"],
+        ["0 ^ 5000000000", "0"],
+        ["(-1) ^ 5000000000", "1"],
+        ["(-1) ^ 5000000001", "-1"],
+    ];
+    for [code, expected] in input {
+        assert_eq!(eval(code).unwrap(), expected, "{}", code);
+    }
+}
+
+#[test]
+fn test_eval_real_arithmetic() {
+    let input = [
+        ["2.5", "2.5"],
+        ["-2.5", "-2.5"],
+        ["1.5 + 1.5", "3.0"],
+        ["1 + 0.5", "1.5"],
+        ["0.5 * 4", "2.0"],
+        ["7 / 2", "3.5"],
+        ["6 / 3", "2.0"],
+        ["1.0 / 4", "0.25"],
+        ["1 < 1.5", "true"],
+        ["2 == 2.0", "true"],
+        ["2.0 != 2", "false"],
+        ["2 ^ -1", "0.5"],
+        ["4 ^ 0.5", "2.0"],
+        ["1.5 ^ 2", "2.25"],
+    ];
+    for [code, expected] in input {
+        assert_eq!(eval(code).unwrap(), expected, "{}", code);
+    }
+    assert_eq!(eval("1 / 0"), Err(RuntimeError::DivisionByZero));
+    assert_eq!(eval("1.5 / 0.0"), Err(RuntimeError::DivisionByZero));
+    assert_eq!(eval("0 ^ -1"), Err(RuntimeError::DivisionByZero));
+    assert_eq!(eval("10 ^ 100"), Err(RuntimeError::IntegerOverflow));
+    assert_eq!(eval("2 ^ 5000000000"), Err(RuntimeError::IntegerOverflow));
+    assert_eq!(
+        eval("7.5 DIV 2"),
+        Err(RuntimeError::ExpectedInteger(ValueType::Real))
+    );
+    assert_eq!(
+        eval("7 MOD 2.0"),
+        Err(RuntimeError::ExpectedInteger(ValueType::Real))
+    );
+}
+
+#[test]
+fn test_eval_boolean_expressions() {
+    let input = [
+        ["true", "true"],
+        ["NOT true", "false"],
+        ["1 < 2", "true"],
+        ["1 >= 2", "false"],
+        ["1 == 1", "true"],
+        ["true != false", "true"],
+        ["1 < 2 AND 3 > 4", "false"],
+        ["1 < 2 OR 3 > 4", "true"],
+        ["NOT (1 == 2)", "true"],
+    ];
+    for [code, expected] in input {
+        assert_eq!(eval(code).unwrap(), expected, "{}", code);
+    }
+}
+
+#[test]
+fn test_eval_strings() {
+    let input = [
+        ["\"hello\"", "hello"],
+        ["\"foo\" + \"bar\"", "foobar"],
+        ["\"a\" + \"\" + \"b\"", "ab"],
+        ["\"abc\" == \"abc\"", "true"],
+        ["\"abc\" != \"abd\"", "true"],
+    ];
+    for [code, expected] in input {
+        assert_eq!(eval(code).unwrap(), expected, "{}", code);
+    }
+    assert_eq!(
+        eval("\"a\" + 1"),
+        Err(RuntimeError::InvalidInfixOperands(
+            InfixOperator::Plus,
+            ValueType::String,
+            ValueType::Integer
+        ))
+    );
+}
+
+#[test]
+fn test_eval_string_methods() {
+    let input = [
+        ["\"hello\".length", "5"],
+        ["\"\".length", "0"],
+        ["\"héllo\".length", "5"],
+        ["\"Hello\".upper", "HELLO"],
+        ["\"Hello\".lower", "hello"],
+        ["\"Hello\".substring(1, 3)", "ell"],
+        ["\"Hello\".substring(5, 0)", ""],
+        ["\"Hello\".left(2)", "He"],
+        ["\"Hello\".right(3)", "llo"],
+        ["\"héllo\".right(4).upper", "ÉLLO"],
+        ["array a[4]\na.length", "4"],
+    ];
+    for [code, expected] in input {
+        assert_eq!(eval(code).unwrap(), expected, "{}", code);
+    }
+    assert_eq!(
+        eval("\"Hello\".substring(3, 3)"),
+        Err(RuntimeError::SubstringOutOfBounds {
+            start:  3,
+            count:  3,
+            length: 5,
+        })
+    );
+    assert_eq!(
+        eval("\"Hello\".right(6)"),
+        Err(RuntimeError::SubstringOutOfBounds {
+            start:  -1,
+            count:  6,
+            length: 5,
+        })
+    );
+    assert_eq!(
+        eval("\"abc\".substring(1, 170141183460469231731687303715884105727)"),
+        Err(RuntimeError::SubstringOutOfBounds {
+            start:  1,
+            count:  i128::MAX,
+            length: 3,
+        })
+    );
+    assert_eq!(
+        eval("\"abc\".right(-170141183460469231731687303715884105727)"),
+        Err(RuntimeError::SubstringOutOfBounds {
+            start:  3,
+            count:  -i128::MAX,
+            length: 3,
+        })
+    );
+    assert_eq!(
+        eval("\"abc\".substring(2, 5)").unwrap_err().to_string(),
+        "a substring of 5 character(s) from position 2 does not fit in a string of length 3"
+    );
+    assert_eq!(
+        eval("\"Hello\".left(1, 2)"),
+        Err(RuntimeError::WrongArgumentCount {
+            func:     "left".to_owned(),
+            expected: 1,
+            found:    2,
+        })
+    );
+    assert_eq!(
+        eval("\"Hello\".left(true)"),
+        Err(RuntimeError::ExpectedInteger(ValueType::Boolean))
+    );
+    assert_eq!(
+        eval("\"Hello\".size"),
+        Err(RuntimeError::NoSuchMember(
+            "string".to_owned(),
+            "size".to_owned()
+        ))
+    );
+    assert_eq!(
+        eval("\"Hello\".length()"),
+        Err(RuntimeError::NoSuchMember(
+            "string".to_owned(),
+            "length".to_owned()
+        ))
+    );
+}
+
+#[test]
+fn test_eval_short_circuit() {
+    // If the right hand side was evaluated then this would error
+    assert_eq!(eval("false AND undefined").unwrap(), "false");
+    assert_eq!(eval("true OR undefined").unwrap(), "true");
+}
+
+#[test]
+fn test_eval_assign_statement() {
+    let input = "a = 5
+b = a * 2
+a = b + a";
+    let prog = parse_from_string(input).unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.run(&prog).unwrap();
+    assert_eq!(interpreter.get_global("a"), Some(Value::Integer(15)));
+    assert_eq!(interpreter.get_global("b"), Some(Value::Integer(10)));
+}
+
+#[test]
+fn test_eval_if_statement() {
+    let input = "x = 10
+if x > 5 then
+    y = 1
+else
+    y = 2
+endif
+if x < 5 then
+    z = 1
+endif";
+    let prog = parse_from_string(input).unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.run(&prog).unwrap();
+    assert_eq!(interpreter.get_global("y"), Some(Value::Integer(1)));
+    assert_eq!(interpreter.get_global("z"), None);
+}
+
+#[test]
+fn test_eval_elseif_chain() {
+    let input = "function grade(mark)
+    if mark >= 70 then
+        return \"A\"
+    elseif mark >= 60 then
+        return \"B\"
+    elseif mark >= 50 then
+        return \"C\"
+    else
+        return \"U\"
+    endif
+endfunction
+grade(75) + grade(65) + grade(60) + grade(55) + grade(10)";
+    assert_eq!(eval(input).unwrap(), "ABBCU");
+
+    let input = "if false then
+    x = 1
+elseif true then
+    x = 2
+elseif true then
+    x = 3
+endif
+x";
+    assert_eq!(eval(input).unwrap(), "2");
+}
+
+#[test]
+fn test_eval_function_call() {
+    let input = "function add(a, b)
+    return a + b
+endfunction
+add(2, add(3, 4))";
+    assert_eq!(eval(input).unwrap(), "9");
+}
+
+#[test]
+fn test_eval_by_ref_parameters() {
+    let input = "procedure swap(a:byRef, b:byRef)
+    temp = a
+    a = b
+    b = temp
+endprocedure
+x = 1
+y = 2
+swap(x, y)
+array arr[2]
+arr[0] = 3
+arr[1] = 4
+swap(arr[0], arr[1])
+str(x) + str(y) + str(arr[0]) + str(arr[1])";
+    assert_eq!(eval(input).unwrap(), "2143");
+
+    let input = "class Counter
+    public count
+    public procedure new()
+        count = 0
+    endprocedure
+endclass
+procedure increment(n:byRef)
+    n = n + 1
+endprocedure
+c = new Counter()
+increment(c.count)
+increment(c.count)
+c.count";
+    assert_eq!(eval(input).unwrap(), "2");
+
+    assert_eq!(
+        eval(
+            "procedure increment(n:byRef)
+n = n + 1
+endprocedure
+increment(1)"
+        ),
+        Err(RuntimeError::InvalidByRefArgument("n".to_owned()))
+    );
+
+    // Both parameters refer to the same variable
+    let input = "procedure both(p:byRef, q:byRef)
+    p = 1
+    q = 2
+    global seen = p
+endprocedure
+x = 0
+both(x, x)
+str(seen) + str(x)";
+    assert_eq!(eval(input).unwrap(), "22");
+
+    // The index is only evaluated once
+    let input = "calls = 0
+function idx()
+    global calls = calls + 1
+    return 0
+endfunction
+procedure increment(n:byRef)
+    n = n + 1
+endprocedure
+array a[1]
+a[0] = 5
+increment(a[idx()])
+str(a[0]) + str(calls)";
+    assert_eq!(eval(input).unwrap(), "61");
+}
+
+#[test]
+fn test_eval_by_val_parameters() {
+    let input = "procedure change(a:byVal, b)
+    a[0] = 9
+    b[0] = 9
+endprocedure
+array x[1]
+array y[1]
+x[0] = 1
+y[0] = 1
+change(x, y)
+str(x[0]) + str(y[0])";
+    // Parameters without `byVal` or `byRef` are passed by value
+    assert_eq!(eval(input).unwrap(), "11");
+}
+
+#[test]
+fn test_eval_recursive_function() {
+    let input = "function fib(n)
+    if n <= 1 then
+        return n
+    endif
+    return fib(n - 1) + fib(n - 2)
+endfunction
+fib(15)";
+    assert_eq!(eval(input).unwrap(), "610");
+}
+
+#[test]
+fn test_eval_procedure_scope() {
+    let input = "x = 1
+procedure set_globals()
+    x = 2
+    local = 3
+    global g = 4
+    g = g + 1
+    global x = x + 5
+endprocedure
+set_globals()";
+    let prog = parse_from_string(input).unwrap();
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.run(&prog).unwrap(), Value::Nothing);
+    // `x = 2` makes a local variable, which the global `x` is then set from
+    assert_eq!(interpreter.get_global("x"), Some(Value::Integer(7)));
+    assert_eq!(interpreter.get_global("local"), None);
+    assert_eq!(interpreter.get_global("g"), Some(Value::Integer(5)));
+}
+
+#[test]
+fn test_eval_parameters_shadow_globals() {
+    let input = "n = 1
+function double(n)
+    n = n * 2
+    return n
+endfunction
+double(5)";
+    let prog = parse_from_string(input).unwrap();
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.run(&prog).unwrap(), Value::Integer(10));
+    assert_eq!(interpreter.get_global("n"), Some(Value::Integer(1)));
+}
+
+#[test]
+fn test_eval_runtime_errors() {
+    assert_eq!(
+        eval("missing + 1"),
+        Err(RuntimeError::UndefinedVariable("missing".to_owned()))
+    );
+    assert_eq!(eval("5 DIV 0"), Err(RuntimeError::DivisionByZero));
+    assert_eq!(
+        eval("1 + true"),
+        Err(RuntimeError::InvalidInfixOperands(
+            InfixOperator::Plus,
+            ValueType::Integer,
+            ValueType::Boolean
+        ))
+    );
+    assert_eq!(
+        eval("if 1 then\nx = 1\nendif"),
+        Err(RuntimeError::NonBooleanCondition(ValueType::Integer))
+    );
+    assert_eq!(
+        eval("x = 1\nx()"),
+        Err(RuntimeError::NotCallable("x".to_owned()))
+    );
+    assert_eq!(
+        eval("function f(a)\nreturn a\nendfunction\nf(1, 2)"),
+        Err(RuntimeError::WrongArgumentCount {
+            func:     "f".to_owned(),
+            expected: 1,
+            found:    2,
+        })
+    );
+    assert_eq!(
+        eval("function f()\nreturn f()\nendfunction\nf()"),
+        Err(RuntimeError::StackOverflow)
+    );
+    assert_eq!(eval("return 1"), Err(RuntimeError::ReturnOutsideFunction));
+}
+
+#[test]
+fn test_eval_for_statement() {
+    let input = "total = 0
+for i = 1 to 10
+    total = total + i
+next i
+evens = 0
+for i = 10 to 0 step -2
+    evens = evens + 1
+next i";
+    let prog = parse_from_string(input).unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.run(&prog).unwrap();
+    assert_eq!(interpreter.get_global("total"), Some(Value::Integer(55)));
+    assert_eq!(interpreter.get_global("evens"), Some(Value::Integer(6)));
+    assert_eq!(interpreter.get_global("i"), Some(Value::Integer(0)));
+
+    assert_eq!(
+        eval("for i = 0 to 1 step 0\nnext i"),
+        Err(RuntimeError::ZeroLoopStep)
+    );
+}
+
+#[test]
+fn test_eval_while_statement() {
+    let input = "x = 1
+count = 0
+while x < 100
+    x = x * 2
+    count = count + 1
+endwhile
+while false
+    count = 0
+endwhile";
+    let prog = parse_from_string(input).unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.run(&prog).unwrap();
+    assert_eq!(interpreter.get_global("x"), Some(Value::Integer(128)));
+    assert_eq!(interpreter.get_global("count"), Some(Value::Integer(7)));
+}
+
+#[test]
+fn test_eval_do_until_statement() {
+    let input = "x = 0
+do
+    x = x + 1
+until true
+y = 0
+do
+    y = y + 3
+until y > 10";
+    let prog = parse_from_string(input).unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.run(&prog).unwrap();
+    assert_eq!(interpreter.get_global("x"), Some(Value::Integer(1)));
+    assert_eq!(interpreter.get_global("y"), Some(Value::Integer(12)));
+}
+
+#[test]
+fn test_eval_switch_statement() {
+    let input = "function name(n)
+    switch n:
+        case 1:
+            return 10
+        case true:
+            return 20
+        case 1 + 1:
+            return 30
+        default:
+            return 40
+    endswitch
+endfunction
+procedure nothing(n)
+    switch n:
+        case 1:
+            global hit = true
+    endswitch
+endprocedure";
+    for [call, expected] in [["name(1)", "10"], ["name(2)", "30"], ["name(3)", "40"], [
+        "nothing(2)",
+        "",
+    ]] {
+        assert_eq!(eval(&(input.to_owned() + "\n" + call)).unwrap(), expected);
+    }
+    // Cases are compared like `==`, so a real can match an integer
+    assert_eq!(eval(&(input.to_owned() + "\nname(4 / 2)")).unwrap(), "30");
+}
+
+#[test]
+fn test_eval_arrays() {
+    let input = "array names[3]
+names[0] = 1
+names[2] = names[0] + 1
+array board[2, 3]
+for x = 0 to 1
+    for y = 0 to 2
+        board[x, y] = x * 3 + y
+    next y
+next x
+procedure clear(a:byRef)
+    a[1] = 0
+endprocedure
+clear(names)";
+    let prog = parse_from_string(input).unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.run(&prog).unwrap();
+    assert_eq!(
+        interpreter.get_global("names").unwrap().to_string(),
+        "[1, 0, 2]"
+    );
+    assert_eq!(
+        interpreter.get_global("board").unwrap().to_string(),
+        "[[0, 1, 2], [3, 4, 5]]"
+    );
+}
+
+#[test]
+fn test_eval_array_errors() {
+    assert_eq!(
+        eval("array a[2]\na[2]"),
+        Err(RuntimeError::IndexOutOfBounds {
+            index:  2,
+            length: 2,
+        })
+    );
+    assert_eq!(
+        eval("array a[2]\na[-1] = 1"),
+        Err(RuntimeError::IndexOutOfBounds {
+            index:  -1,
+            length: 2,
+        })
+    );
+    assert_eq!(
+        eval("array a[2]\na[0, 0]"),
+        Err(RuntimeError::NotIndexable(ValueType::Nothing))
+    );
+    assert_eq!(
+        eval("x = 1\nx[0]"),
+        Err(RuntimeError::NotIndexable(ValueType::Integer))
+    );
+    assert_eq!(
+        eval("array a[0 - 1]"),
+        Err(RuntimeError::InvalidArraySize(-1))
+    );
+    assert_eq!(
+        eval("array a[99999999999999]"),
+        Err(RuntimeError::ArrayTooLarge)
+    );
+    assert_eq!(
+        eval("array a[100000000, 0]"),
+        Err(RuntimeError::ArrayTooLarge)
+    );
+    assert_eq!(
+        eval("array a[true]"),
+        Err(RuntimeError::ExpectedInteger(ValueType::Boolean))
+    );
+}
+
+#[test]
+fn test_eval_classes() {
+    let input = "class Pet
+    private name
+    public legs
+    public procedure new(givenName)
+        name = givenName
+        legs = 4
+    endprocedure
+    public function getName()
+        return name
+    endfunction
+    public function sameName(other)
+        return name == other.name
+    endfunction
+    public function shout()
+        return getName().upper
+    endfunction
+endclass
+class Dog inherits Pet
+    private breed
+    public procedure new(givenName, givenBreed)
+        super.new(givenName)
+        breed = givenBreed
+    endprocedure
+    public function getName()
+        return super.getName() + \" the \" + breed
+    endfunction
+endclass
+rex = new Dog(\"Rex\", \"Terrier\")
+pet = new Pet(\"Tom\")
+pet.legs = 3";
+    for [code, expected] in [
+        ["rex.getName()", "Rex the Terrier"],
+        ["pet.getName()", "Tom"],
+        ["pet.shout()", "TOM"],
+        ["rex.shout()", "REX THE TERRIER"],
+        ["rex.legs", "4"],
+        ["pet.legs", "3"],
+        ["pet.sameName(new Pet(\"Tom\"))", "true"],
+        ["pet.sameName(rex)", "false"],
+        ["rex", "<Dog object>"],
+        ["Pet", "<class Pet>"],
+        ["rex == rex", "true"],
+        ["rex == new Dog(\"Rex\", \"Terrier\")", "false"],
+    ] {
+        assert_eq!(
+            eval(&(input.to_owned() + "\n" + code)).unwrap(),
+            expected,
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn test_eval_class_errors() {
+    let input = "class Pet
+    private name
+    private procedure secret()
+    endprocedure
+endclass
+pet = new Pet()
+";
+    assert_eq!(
+        eval(&(input.to_owned() + "pet.name")),
+        Err(RuntimeError::PrivateMember(
+            "Pet".to_owned(),
+            "name".to_owned()
+        ))
+    );
+    assert_eq!(
+        eval(&(input.to_owned() + "pet.name = 1")),
+        Err(RuntimeError::PrivateMember(
+            "Pet".to_owned(),
+            "name".to_owned()
+        ))
+    );
+    assert_eq!(
+        eval(&(input.to_owned() + "pet.secret()")),
+        Err(RuntimeError::PrivateMember(
+            "Pet".to_owned(),
+            "secret".to_owned()
+        ))
+    );
+    // Members are private to the class which declares them
+    let dog = "class Dog inherits Pet
+endclass
+dog = new Dog()
+";
+    assert_eq!(
+        eval(&(input.to_owned() + dog + "dog.name")),
+        Err(RuntimeError::PrivateMember(
+            "Pet".to_owned(),
+            "name".to_owned()
+        ))
+    );
+    assert_eq!(
+        eval(&(input.to_owned() + dog + "dog.secret()")),
+        Err(RuntimeError::PrivateMember(
+            "Pet".to_owned(),
+            "secret".to_owned()
+        ))
+    );
+    assert_eq!(
+        eval(&(input.to_owned() + "pet.age")),
+        Err(RuntimeError::NoSuchMember(
+            "Pet".to_owned(),
+            "age".to_owned()
+        ))
+    );
+    assert_eq!(
+        eval(&(input.to_owned() + "new Pet(1)")),
+        Err(RuntimeError::WrongArgumentCount {
+            func:     "new".to_owned(),
+            expected: 0,
+            found:    1,
+        })
+    );
+    assert_eq!(
+        eval(&(input.to_owned() + "x = 1\nnew x()")),
+        Err(RuntimeError::NotAClass("x".to_owned()))
+    );
+    assert_eq!(
+        eval("class Cat inherits Pet\nendclass"),
+        Err(RuntimeError::UndefinedVariable("Pet".to_owned()))
+    );
+    assert_eq!(
+        eval(&(input.to_owned() + "pet.secret.x")),
+        Err(RuntimeError::NoSuchMember(
+            "Pet".to_owned(),
+            "secret".to_owned()
+        ))
+    );
+    assert_eq!(
+        eval("x = 1\nx.y"),
+        Err(RuntimeError::NoSuchMember(
+            "integer".to_owned(),
+            "y".to_owned()
+        ))
+    );
+    assert_eq!(eval("super.new()"), Err(RuntimeError::InvalidSuper));
+    assert_eq!(
+        eval(
+            &(input.to_owned()
+                + "class Cat\nprocedure new()\nsuper.new()\nendprocedure\nendclass\nnew Cat()")
+        ),
+        Err(RuntimeError::InvalidSuper)
+    );
+}
+
+#[test]
+fn test_eval_print_and_input() {
+    let input = "name = input(\"Name: \")
+age = input(\"Age: \")
+print(\"Hello \" + name)
+print(age, 1 + 1, true)
+print()";
+    let prog = parse_from_string(input).unwrap();
+    let mut io = BufferIo::new(["Bob", "17"]);
+    Interpreter::with_io(&mut io).run(&prog).unwrap();
+    assert_eq!(io.output, "Name: Bob\nAge: 17\nHello Bob\n17 2 true\n\n");
+    assert!(io.input.is_empty());
+}
+
+#[test]
+fn test_eval_io_errors() {
+    let prog = parse_from_string("input(\"> \")").unwrap();
+    assert_eq!(
+        Interpreter::with_io(BufferIo::default()).run(&prog),
+        Err(RuntimeError::EndOfInput)
+    );
+    assert_eq!(
+        eval("input()"),
+        Err(RuntimeError::WrongArgumentCount {
+            func:     "input".to_owned(),
+            expected: 1,
+            found:    0,
+        })
+    );
+
+    // Programs can declare their own functions with the same names
+    assert_eq!(
+        eval("function input(prompt)\nreturn prompt + \"!\"\nendfunction\ninput(\"hi\")").unwrap(),
+        "hi!"
+    );
+}
+
+#[test]
+fn test_eval_files() {
+    let input = "newFile(\"out.txt\")
+out = open(\"out.txt\")
+scores = open(\"scores.txt\")
+total = 0
+while NOT scores.endOfFile()
+    line = scores.readLine()
+    out.writeLine(\"read \" + line)
+    total = total + 1
+endwhile
+scores.close()
+out.writeLine(total)
+out.close()";
+    let prog = parse_from_string(input).unwrap();
+    let mut fs = MemoryFileSystem::default();
+    fs.files
+        .insert("scores.txt".to_owned(), "10\r\n\n30".to_owned());
+    Interpreter::with_io_and_fs(BufferIo::default(), &mut fs)
+        .run(&prog)
+        .unwrap();
+    assert_eq!(fs.files["out.txt"], "read 10\nread \nread 30\n3\n");
+    assert_eq!(fs.files["scores.txt"], "10\r\n\n30");
+}
+
+#[test]
+fn test_eval_file_errors() {
+    let run = |input: &str| {
+        let prog = parse_from_string(input).unwrap();
+        let mut fs = MemoryFileSystem::default();
+        fs.files.insert("a.txt".to_owned(), "line".to_owned());
+        let result = Interpreter::with_io_and_fs(BufferIo::default(), fs)
+            .run(&prog)
+            .map(|v| v.to_string());
+        result
+    };
+    assert_eq!(run("open(\"a.txt\")").unwrap(), "<file a.txt>");
+    assert_eq!(
+        run("f = open(\"a.txt\")\nf.readLine()\nf.readLine()"),
+        Err(RuntimeError::EndOfFile("a.txt".to_owned()))
+    );
+    assert_eq!(
+        run("f = open(\"a.txt\")\nf.close()\nf.readLine()"),
+        Err(RuntimeError::FileClosed("a.txt".to_owned()))
+    );
+    assert_eq!(
+        run("f = open(\"a.txt\")\nf.writeLine()"),
+        Err(RuntimeError::WrongArgumentCount {
+            func:     "writeLine".to_owned(),
+            expected: 1,
+            found:    0,
+        })
+    );
+    assert!(matches!(
+        run("open(\"missing.txt\")"),
+        Err(RuntimeError::Io(e)) if e.starts_with("missing.txt: ")
+    ));
+}
+
+#[test]
+fn test_eval_conversions() {
+    let input = [
+        ["str(12) + str(1.5)", "121.5"],
+        ["str(true)", "true"],
+        ["int(\"42\") + 1", "43"],
+        ["int(\" -7 \")", "-7"],
+        ["int(3.9)", "3"],
+        ["int(-3.9)", "-3"],
+        ["float(\"2.5\")", "2.5"],
+        ["real(3)", "3.0"],
+        ["real(\"1e3\")", "1000.0"],
+        ["bool(\"True\")", "true"],
+        ["bool(\"false\")", "false"],
+        ["ASC(\"A\")", "65"],
+        ["ASC(\"é\")", "233"],
+        ["CHR(97)", "a"],
+        ["CHR(ASC(\"a\") + 1)", "b"],
+    ];
+    for [code, expected] in input {
+        assert_eq!(eval(code).unwrap(), expected, "{}", code);
+    }
+
+    let invalid = [
+        ["int(\"abc\")", "abc", "integer"],
+        ["int(\"1.5\")", "1.5", "integer"],
+        ["int(true)", "true", "integer"],
+        ["real(\"pi\")", "pi", "real"],
+        ["bool(\"yes\")", "yes", "boolean"],
+        ["bool(1)", "1", "boolean"],
+        ["ASC(\"ab\")", "ab", "integer"],
+        ["ASC(\"\")", "", "integer"],
+        ["CHR(-1)", "-1", "string"],
+    ];
+    for [code, value, to] in invalid {
+        match eval(code) {
+            Err(RuntimeError::InvalidConversion(v, t)) => {
+                assert_eq!(v, value, "{}", code);
+                assert_eq!(t.to_string(), to, "{}", code);
+            }
+            other => panic!("{} gave {:?}", code, other),
+        }
+    }
+    assert_eq!(
+        eval("CHR(\"a\")"),
+        Err(RuntimeError::ExpectedInteger(ValueType::String))
+    );
+    assert_eq!(
+        eval("str(1, 2)"),
+        Err(RuntimeError::WrongArgumentCount {
+            func:     "str".to_owned(),
+            expected: 1,
+            found:    2,
+        })
+    );
+}
+
+#[test]
+fn test_eval_random() {
+    for _ in 0..50 {
+        let n: i128 = eval("random(1, 6)").unwrap().parse().unwrap();
+        assert!((1..=6).contains(&n), "{}", n);
+        let r: f64 = eval("random(1.0, 2)").unwrap().parse().unwrap();
+        assert!((1.0..2.0).contains(&r), "{}", r);
+    }
+    assert_eq!(eval("random(3, 3)").unwrap(), "3");
+    assert_eq!(eval("random(6, 1)"), Err(RuntimeError::InvalidRandomRange));
+    assert_eq!(
+        eval("random(\"1\", 2)"),
+        Err(RuntimeError::ExpectedNumber(ValueType::String))
+    );
+}
+
+#[test]
+fn test_eval_seeded_random() {
+    let input = "for i = 1 to 5
+    print(random(1, 6), random(0.0, 1.0), random(-1000000, 1000000))
+next i";
+    let prog = parse_from_string(input).unwrap();
+    let run = |seed| {
+        let mut io = BufferIo::default();
+        let mut interpreter = Interpreter::with_io(&mut io);
+        interpreter.set_random_seed(seed);
+        interpreter.run(&prog).unwrap();
+        drop(interpreter);
+        io.output
+    };
+    assert_eq!(run(42), run(42));
+    assert_ne!(run(42), run(43));
+}
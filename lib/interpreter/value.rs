@@ -0,0 +1,222 @@
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Environment;
+use super::File;
+use crate::syntax::ClassAttribute;
+use crate::syntax::ClassMethod;
+use crate::syntax::ClassStatement;
+use crate::syntax::FunctionStatement;
+
+/// A value produced while evaluating a program. Functions keep a reference to
+/// their declaration in the AST, so a `Value` can never outlive the `Program`
+/// it came from.
+#[derive(Debug, Clone)]
+pub enum Value<'a> {
+    Integer(i128),
+    Real(f64),
+    Boolean(bool),
+    String(String),
+    Function(Function<'a>),
+
+    /// Arrays are shared rather than copied when assigned, but are copied when
+    /// passed to a function by value. A 2D array is an array of arrays.
+    Array(Array<'a>),
+
+    Class(Rc<Class<'a>>),
+
+    /// Like arrays, objects are shared rather than copied.
+    Object(Object<'a>),
+
+    File(File),
+
+    /// Produced by procedures, functions which finish without a `return` and
+    /// statements which don't evaluate to anything.
+    Nothing,
+}
+impl Display for Value<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Integer(i) => write!(f, "{}", i),
+            // Debug formatting keeps the `.0` on whole numbers
+            Self::Real(r) => write!(f, "{:?}", r),
+            Self::Boolean(b) => write!(f, "{}", b),
+            Self::String(s) => write!(f, "{}", s),
+            Self::Function(func) => write!(f, "<{}>", func.decl.ident.get_ident()),
+            Self::Array(a) => {
+                write!(f, "[")?;
+                for (i, v) in a.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                write!(f, "]")
+            }
+            Self::Class(c) => write!(f, "<class {}>", c.name()),
+            Self::Object(o) => write!(f, "<{} object>", o.class.name()),
+            Self::File(file) => write!(f, "<file {}>", file.borrow().path),
+            Self::Nothing => Ok(()),
+        }
+    }
+}
+impl PartialEq for Value<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Integer(l), Self::Integer(r)) => l == r,
+            (Self::Real(l), Self::Real(r)) => l == r,
+            (Self::Boolean(l), Self::Boolean(r)) => l == r,
+            (Self::String(l), Self::String(r)) => l == r,
+            (Self::Function(l), Self::Function(r)) => std::ptr::eq(l.decl, r.decl),
+            (Self::Array(l), Self::Array(r)) => Rc::ptr_eq(l, r) || *l.borrow() == *r.borrow(),
+            (Self::Class(l), Self::Class(r)) => std::ptr::eq(l.decl, r.decl),
+            (Self::Object(l), Self::Object(r)) => Rc::ptr_eq(&l.fields, &r.fields),
+            (Self::File(l), Self::File(r)) => Rc::ptr_eq(l, r),
+            (Self::Nothing, Self::Nothing) => true,
+            _ => false,
+        }
+    }
+}
+
+/// The shared storage behind `Value::Array`
+pub type Array<'a> = Rc<RefCell<Vec<Value<'a>>>>;
+
+/// A function or procedure along with the environment it was declared in.
+#[derive(Clone)]
+pub struct Function<'a> {
+    pub decl: &'a FunctionStatement<'a>,
+    pub env:  Rc<RefCell<Environment<'a>>>,
+}
+impl Debug for Function<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The environment can contain this very function, so printing it would
+        // recurse forever
+        f.debug_struct("Function")
+            .field("ident", &self.decl.ident.get_ident())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A class along with the class it inherits from and the environment it was
+/// declared in.
+pub struct Class<'a> {
+    pub decl:   &'a ClassStatement<'a>,
+    pub parent: Option<Rc<Class<'a>>>,
+    pub env:    Rc<RefCell<Environment<'a>>>,
+}
+impl<'a> Class<'a> {
+    pub fn name(&self) -> &'a str {
+        self.decl.ident.get_ident()
+    }
+
+    /// Find a method in this class or the nearest parent class which has it,
+    /// along with the class it was found in.
+    pub fn find_method(self: &Rc<Self>, name: &str) -> Option<(&'a ClassMethod<'a>, Rc<Self>)> {
+        match self
+            .decl
+            .methods
+            .iter()
+            .find(|m| m.func.ident.get_ident() == name)
+        {
+            Some(m) => Some((m, self.clone())),
+            None => self.parent.as_ref()?.find_method(name),
+        }
+    }
+
+    /// Find an attribute in this class or the nearest parent class which has
+    /// it, along with the class it was found in.
+    pub fn find_attribute(&self, name: &str) -> Option<(&'a ClassAttribute<'a>, &Self)> {
+        match self
+            .decl
+            .attributes
+            .iter()
+            .find(|a| a.ident.get_ident() == name)
+        {
+            Some(a) => Some((a, self)),
+            None => self.parent.as_ref()?.find_attribute(name),
+        }
+    }
+
+    /// Whether this is `other` or inherits from it, directly or not
+    pub fn is_subclass_of(&self, other: &Class) -> bool {
+        std::ptr::eq(self.decl, other.decl)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|p| p.is_subclass_of(other))
+    }
+}
+impl Debug for Class<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Class")
+            .field("ident", &self.name())
+            .finish_non_exhaustive()
+    }
+}
+
+/// An instance of a class. The attributes of the class and all of its parents
+/// are stored in `fields`, which is enclosed by the environment the class was
+/// declared in so that methods can see everything the class can.
+#[derive(Clone)]
+pub struct Object<'a> {
+    pub class:  Rc<Class<'a>>,
+    pub fields: Rc<RefCell<Environment<'a>>>,
+}
+impl Debug for Object<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The fields can contain this very object
+        f.debug_struct("Object")
+            .field("class", &self.class.name())
+            .finish_non_exhaustive()
+    }
+}
+
+// Variation of the Value enum except with no attached data, only the variant
+// type data is stored
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ValueType {
+    Integer,
+    Real,
+    Boolean,
+    String,
+    Function,
+    Array,
+    Class,
+    Object,
+    File,
+    Nothing,
+}
+impl From<&Value<'_>> for ValueType {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Integer(_) => Self::Integer,
+            Value::Real(_) => Self::Real,
+            Value::Boolean(_) => Self::Boolean,
+            Value::String(_) => Self::String,
+            Value::Function(_) => Self::Function,
+            Value::Array(_) => Self::Array,
+            Value::Class(_) => Self::Class,
+            Value::Object(_) => Self::Object,
+            Value::File(_) => Self::File,
+            Value::Nothing => Self::Nothing,
+        }
+    }
+}
+impl Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Integer => "integer",
+            Self::Real => "real",
+            Self::Boolean => "boolean",
+            Self::String => "string",
+            Self::Function => "function",
+            Self::Array => "array",
+            Self::Class => "class",
+            Self::Object => "object",
+            Self::File => "file",
+            Self::Nothing => "nothing",
+        })
+    }
+}
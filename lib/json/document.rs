@@ -0,0 +1,66 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::lexer::Interner;
+use crate::parser::parse_with_recovery;
+use crate::parser::ParserError;
+use crate::syntax::owned;
+use crate::syntax::IntoOwned;
+use crate::syntax::Stmt;
+
+/// The version of the JSON layout, written to every document
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Everything known about a source file after parsing it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(deserialize = "'de: 'a"))]
+pub struct Document<'a> {
+    pub version:    u32,
+    pub statements: Vec<Stmt<'a>>,
+    pub errors:     Vec<ErrorReport>,
+}
+impl<'a> Document<'a> {
+    /// Parse the input with recovery, so the document has every error and a
+    /// program which is as complete as possible
+    pub fn parse(input: &'a str) -> Self {
+        let (prog, errors) = parse_with_recovery(input);
+        Self {
+            version:    SCHEMA_VERSION,
+            statements: prog.statements,
+            errors:     errors.into_iter().map(ErrorReport::from).collect(),
+        }
+    }
+}
+
+/// A parser error along with the message it is displayed with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub message: String,
+    pub error:   ParserError,
+}
+impl From<ParserError> for ErrorReport {
+    fn from(error: ParserError) -> Self {
+        Self {
+            message: error.to_string(),
+            error,
+        }
+    }
+}
+
+/// Parse the input and write it out as a pretty printed JSON document
+pub fn to_json(input: &str) -> String {
+    serde_json::to_string_pretty(&Document::parse(input)).unwrap()
+}
+
+/// Read back a program and its errors from a JSON document. The program is
+/// owned, so it doesn't borrow from the JSON.
+pub fn from_json(json: &str) -> serde_json::Result<(owned::Program, Vec<ParserError>)> {
+    // Strings in the JSON text may have escapes, so they can only be borrowed
+    // once they have been read into a value
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let doc = Document::deserialize(&value)?;
+    let prog = owned::Program {
+        statements: doc.statements.into_owned(&Interner::new()),
+    };
+    Ok((prog, doc.errors.into_iter().map(|e| e.error).collect()))
+}
@@ -0,0 +1,29 @@
+//! Integer literals are held as an `i128`, which JSON tools and serde's
+//! internally tagged enums can't cope with. They are written as a number when
+//! they fit in an `i64`, and as a string of digits when they don't.
+
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+pub(crate) fn serialize<S: Serializer>(value: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+    match i64::try_from(*value) {
+        Ok(i) => i.serialize(serializer),
+        Err(_) => value.to_string().serialize(serializer),
+    }
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i128, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Integer {
+        Number(i64),
+        Digits(String),
+    }
+
+    match Integer::deserialize(deserializer)? {
+        Integer::Number(i) => Ok(i.into()),
+        Integer::Digits(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
@@ -0,0 +1,101 @@
+//! The JSON form of a parsed program, for tools outside of Rust. A document
+//! is an object with these fields:
+//!
+//! - `version`: the [`SCHEMA_VERSION`] it was written with
+//! - `statements`: the statements of the program
+//! - `errors`: each parser error, as `{ "message", "error" }` where `message`
+//!   is the error as it is displayed
+//!
+//! # Common values
+//!
+//! - A span is `{ "start", "end", "line", "col" }`, the same as [`Span`].
+//! - A token is `{ "kind", "value" }`, where `kind` is the variant of [`Token`]
+//!   and `value` is the text of an `Identifier`, `StringLiteral` or
+//!   `NumberLiteral`. Other tokens have no `value`.
+//! - An identifier is `{ "token", "span" }`.
+//! - A block is `{ "token", "span", "statements" }`.
+//! - Optional fields are `null` when they are missing.
+//!
+//! # Statements
+//!
+//! Every statement has a `kind`, then the fields listed for it. All but
+//! `Empty` have a `span`, and all but `Expression`, `Error` and `Empty` have
+//! the `token` they start with.
+//!
+//! - `Assign`: `target`, `global` (a boolean), `value`
+//! - `Return`: `value` (optional)
+//! - `Expression`: `value`
+//! - `If`: `condition`, `consequence` (a block), `elseifs` (a list of
+//!   `[condition, block]` pairs), `alternative` (an optional block)
+//! - `Block`: `statements`
+//! - `Function`: `ident`, `params`, `body` (a block), `is_procedure`. Each
+//!   parameter is `{ "ident", "pass_by" }`, where `pass_by` is `"Value"`,
+//!   `"Reference"` or `null`
+//! - `For`: `counter` (an identifier), `start`, `end`, `step` (optional),
+//!   `body` (a block)
+//! - `While`: `condition`, `body` (a block)
+//! - `DoUntil`: `body` (a block), `condition`
+//! - `Switch`: `subject`, `cases`, `default` (an optional block). Each case is
+//!   `{ "token", "span", "value", "body" }`
+//! - `ArrayDeclaration`: `ident`, `sizes` (a list of expressions)
+//! - `Class`: `ident`, `parent` (an optional identifier), `attributes`,
+//!   `methods`. Attributes are `{ "visibility", "ident" }` and methods are `{
+//!   "visibility", "func" }`, where `func` has the fields of a `Function` and
+//!   `visibility` is `"Public"` or `"Private"`
+//! - `Error`: only a `span`, for a statement which couldn't be parsed
+//! - `Empty`: no other fields
+//!
+//! The `target` of an `Assign` has a `kind` of `Identifier`, `Index` or
+//! `Member`, with the fields of the `Identifier`, `Index` or `MemberAccess`
+//! expression.
+//!
+//! # Expressions
+//!
+//! Every expression has a `kind`, then the fields listed for it. All but
+//! `Placeholder` have a `token` and a `span`.
+//!
+//! - `Identifier`: no other fields, the name is the `value` of the token
+//! - `Boolean`: `value`, a boolean
+//! - `IntegerLiteral`: `value`, a number, or a string of digits when it is too
+//!   large for an `i64`
+//! - `RealLiteral`: `value`, a number
+//! - `StringLiteral`: `value`, the text between the quotes
+//! - `Prefix`: `operator` (`"Plus"`, `"Minus"` or `"Not"`), `subject`
+//! - `Infix`: `operator`, `left`, `right`, where `operator` is a variant of
+//!   [`InfixOperator`] such as `"Plus"` or `"DoubleEquals"`
+//! - `FunctionCall`: `func` (an identifier), `args`
+//! - `Index`: `subject`, `indices`
+//! - `New`: `class` (an identifier), `args`
+//! - `Super`: no other fields
+//! - `MemberAccess`: `subject`, `member` (an identifier)
+//! - `MethodCall`: `subject`, `method` (an identifier), `args`
+//! - `Placeholder`: no other fields
+//!
+//! # Errors
+//!
+//! A parser error is `{ "kind", "value" }`, where `kind` is the variant of
+//! [`ParserError`]. Errors found while lexing (`UnterminatedStringLiteral`,
+//! `InvalidNumberLiteral`, `TooLargeInteger`) and `MismatchedLoopCounter`
+//! have a span as their value. `UnexpectedToken` and `ExpectedExpression`
+//! have the token which was found, as `{ "tok_type", "span" }` where
+//! `tok_type` is the kind of the token. `ExpectedToken` has a pair of the
+//! expected kind and the token which was found.
+//!
+//! Anything which changes this layout bumps [`SCHEMA_VERSION`].
+//!
+//! [`Span`]: crate::lexer::Span
+//! [`Token`]: crate::lexer::Token
+//! [`InfixOperator`]: crate::syntax::InfixOperator
+//! [`ParserError`]: crate::parser::ParserError
+
+mod document;
+pub(crate) mod integer;
+
+#[cfg(test)]
+mod test;
+
+pub use document::from_json;
+pub use document::to_json;
+pub use document::Document;
+pub use document::ErrorReport;
+pub use document::SCHEMA_VERSION;
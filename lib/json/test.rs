@@ -0,0 +1,85 @@
+use super::from_json;
+use super::to_json;
+use super::SCHEMA_VERSION;
+use crate::lexer::Span;
+use crate::lexer::Token;
+use crate::parser::parse_from_string;
+use crate::parser::ParserError;
+use crate::syntax::AsBorrowed;
+
+#[test]
+fn test_json_round_trip() {
+    let input = "x = 3 + foo(\"bar\", 2.5)
+if x > 2 then
+    print(x[1].y)
+elseif x == 1 then
+    x = -x
+endif
+for i = 0 to 10 step 2
+    arr[i] = i
+next i
+y = 170141183460469231731687303715884105727
+path = \"C:\\temp\"
+";
+    let prog = parse_from_string(input).unwrap();
+    let (read, errors) = from_json(&to_json(input)).unwrap();
+    assert!(errors.is_empty());
+    assert_eq!(read.as_borrowed(), prog);
+}
+
+#[test]
+fn test_json_kind_tags() {
+    let json: serde_json::Value = serde_json::from_str(&to_json("x = \"hi\"")).unwrap();
+    assert_eq!(json["version"], SCHEMA_VERSION);
+
+    let assign = &json["statements"][0];
+    assert_eq!(assign["kind"], "Assign");
+    assert_eq!(assign["target"]["kind"], "Identifier");
+    assert_eq!(assign["value"]["kind"], "StringLiteral");
+    assert_eq!(assign["value"]["value"], "hi");
+    assert_eq!(
+        assign["value"]["token"],
+        serde_json::json!({"kind": "StringLiteral", "value": "hi"})
+    );
+    assert_eq!(
+        assign["value"]["span"],
+        serde_json::json!({"start": 4, "end": 8, "line": 1, "col": 5})
+    );
+}
+
+#[test]
+fn test_json_errors() {
+    let input = "x = \ny = 2";
+    let json: serde_json::Value = serde_json::from_str(&to_json(input)).unwrap();
+    let error = &json["errors"][0];
+    assert_eq!(error["message"], "expected an expression, found newline");
+    assert_eq!(error["error"]["kind"], "ExpectedExpression");
+
+    let (prog, errors) = from_json(&to_json(input)).unwrap();
+    assert_eq!(prog.statements.len(), 2);
+    assert!(matches!(errors[..], [ParserError::ExpectedExpression(_)]));
+}
+
+#[test]
+fn test_json_tokens() {
+    let tok = Token::Identifier("count");
+    let json = serde_json::to_string(&tok).unwrap();
+    assert_eq!(json, r#"{"kind":"Identifier","value":"count"}"#);
+    assert_eq!(serde_json::from_str::<Token>(&json).unwrap(), tok);
+
+    let json = serde_json::to_string(&Token::Endwhile).unwrap();
+    assert_eq!(json, r#"{"kind":"Endwhile"}"#);
+    assert_eq!(
+        serde_json::from_str::<Token>(&json).unwrap(),
+        Token::Endwhile
+    );
+
+    let span = Span {
+        start: 1,
+        end:   2,
+        line:  3,
+        col:   4,
+    };
+    let json = serde_json::to_string(&span).unwrap();
+    assert_eq!(json, r#"{"start":1,"end":2,"line":3,"col":4}"#);
+}
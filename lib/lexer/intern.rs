@@ -0,0 +1,42 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+/// Keeps shared copies of strings, for text which has to outlive the source
+/// it came from. Interning equal strings gives back the same copy, so the
+/// memory used only grows with the number of different strings. Each copy is
+/// freed once neither the interner nor anything it was given to holds it.
+#[derive(Debug, Default)]
+pub struct Interner {
+    strings: Mutex<HashSet<Arc<str>>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A shared copy of the string
+    pub fn intern(&self, s: &str) -> Arc<str> {
+        let mut strings = self.strings.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(interned) = strings.get(s) {
+            return Arc::clone(interned);
+        }
+        let interned: Arc<str> = Arc::from(s);
+        strings.insert(Arc::clone(&interned));
+        interned
+    }
+
+    /// The number of different strings which have been interned
+    pub fn len(&self) -> usize {
+        self.strings
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -1,9 +1,14 @@
 use super::tokens::lookup_keyword;
+use super::tokens::lookup_keyword_any_case;
+use crate::lexer::Span;
+use crate::lexer::SpannedToken;
 use crate::lexer::Token;
+use crate::lexer::Trivia;
+use crate::lexer::TriviaToken;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub enum LexerError {
-    UnterminatedStringLiteral,
+    UnterminatedStringLiteral(Span),
 }
 
 #[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
@@ -12,19 +17,43 @@ pub struct Lexer<'a> {
     read_pos: usize,
     input:    &'a str,
     ch:       u8,
+
+    /// Whether keywords are matched regardless of case, see
+    /// `with_any_case_keywords`
+    any_case_keywords: bool,
+
+    // Line counting is done separately from reading characters as `read_pos`
+    // sometimes has to step backwards; `line_pos` is how far through the input
+    // the newlines have been counted
+    line:       usize,
+    line_start: usize,
+    line_pos:   usize,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
         let mut l = Self {
             input,
+            line: 1,
             ..Default::default()
         };
         l.read_char();
         l
     }
 
+    /// Also read keywords written in any case, like `ENDIF` or `Then`. Code is
+    /// case sensitive, so this is only for tools which tidy it up, and names
+    /// like `Next` can't be used as identifiers.
+    pub fn with_any_case_keywords(mut self) -> Self {
+        self.any_case_keywords = true;
+        self
+    }
+
     pub fn next_token(&mut self) -> Result<Token<'a>, LexerError> {
+        Ok(self.next_spanned_token()?.tok)
+    }
+
+    pub fn next_spanned_token(&mut self) -> Result<SpannedToken<'a>, LexerError> {
         self.munch_whitespace();
 
         // Deal with comments
@@ -32,6 +61,7 @@ impl<'a> Lexer<'a> {
             self.skip_to_end_of_line();
         }
 
+        let start = self.pos.min(self.input.len());
         let tok: Token = match self.ch {
             b'+' => Token::Plus,
             b'-' => Token::Minus,
@@ -42,10 +72,12 @@ impl<'a> Lexer<'a> {
             b',' => Token::Comma,
             b'/' => Token::FSlash,
             b':' => Token::Colon,
+            b'.' => Token::Dot,
             b'{' => Token::LSquirly,
             b'}' => Token::RSquirly,
             b'\n' => Token::Newline,
             b'*' => Token::Asterisk,
+            b'^' => Token::Caret,
             0 => Token::Eof,
             b'>' => {
                 if self.peek_char() == b'=' {
@@ -79,10 +111,15 @@ impl<'a> Lexer<'a> {
                     Token::Illegal
                 }
             }
-            b'"' => self.read_string_literal()?,
+            b'"' => self.read_string_literal(start)?,
             _ => {
                 if self.ch.is_ascii_alphabetic() {
-                    lookup_keyword(self.read_identifier())
+                    let ident = self.read_identifier();
+                    if self.any_case_keywords {
+                        lookup_keyword_any_case(ident)
+                    } else {
+                        lookup_keyword(ident)
+                    }
                 } else if self.ch.is_ascii_digit() {
                     self.read_number()?
                 } else {
@@ -91,29 +128,112 @@ impl<'a> Lexer<'a> {
             }
         };
         self.read_char();
-        Ok(tok)
+        let end = self.pos.min(self.input.len());
+        Ok(SpannedToken {
+            tok,
+            span: self.span(start, end),
+        })
     }
 
-    fn read_string_literal(&mut self) -> Result<Token<'a>, LexerError> {
+    /// The next token along with the whitespace and comments before it, which
+    /// `next_spanned_token` skips over. This never fails so that no text is
+    /// lost: an unterminated string literal is an `Illegal` token running to
+    /// the end of the input.
+    pub fn next_token_with_trivia(&mut self) -> TriviaToken<'a> {
+        let trivia_start = self.pos.min(self.input.len());
+        let mut token = match self.next_spanned_token() {
+            Ok(t) => t,
+            Err(LexerError::UnterminatedStringLiteral(span)) => SpannedToken {
+                tok: Token::Illegal,
+                span,
+            },
+        };
+        // Illegal tokens are single bytes, which could be part of a character
+        while !self.input.is_char_boundary(token.span.end) {
+            self.read_char();
+            token.span.end = self.pos;
+        }
+        TriviaToken {
+            leading: Trivia::split(&self.input[trivia_start..token.span.start]),
+            token,
+            text: &self.input[token.span.start..token.span.end],
+        }
+    }
+
+    /// Make the span for a token, counting any newlines since the last one
+    fn span(&mut self, start: usize, end: usize) -> Span {
+        let bytes = self.input.as_bytes();
+        for (i, b) in bytes.iter().enumerate().take(start).skip(self.line_pos) {
+            if *b == b'\n' {
+                self.line += 1;
+                self.line_start = i + 1;
+            }
+        }
+        self.line_pos = self.line_pos.max(start);
+
+        Span {
+            start,
+            end,
+            line: self.line,
+            // Count characters rather than bytes by skipping UTF-8 continuation
+            // bytes
+            col: bytes[self.line_start..start]
+                .iter()
+                .filter(|b| (**b & 0xC0) != 0x80)
+                .count()
+                + 1,
+        }
+    }
+
+    fn read_string_literal(&mut self, start: usize) -> Result<Token<'a>, LexerError> {
         self.read_char();
         let pos = self.pos;
         while self.ch != b'"' && self.ch != 0 {
             self.read_char();
         }
+        if self.ch == 0 {
+            let end = self.input.len();
+            return Err(LexerError::UnterminatedStringLiteral(self.span(start, end)));
+        }
         self.read_pos -= 1;
         self.read_char();
         Ok(Token::StringLiteral(&self.input[pos..self.pos]))
     }
 
+    /// Reads an integer or a real such as `3.14` or `1.5e-3`. The `.` and the
+    /// exponent are only part of the number if there is a digit after them,
+    /// so `1.` is `1` followed by `.` and `2e` is `2` followed by `e`.
     fn read_number(&mut self) -> Result<Token<'a>, LexerError> {
         let pos = self.pos;
-        while self.ch.is_ascii_digit() {
+        self.read_digits();
+        if self.ch == b'.' && self.peek_char().is_ascii_digit() {
             self.read_char();
+            self.read_digits();
+        }
+        if self.ch == b'e' || self.ch == b'E' {
+            let sign = matches!(self.peek_char(), b'+' | b'-') as usize;
+            if self
+                .input
+                .as_bytes()
+                .get(self.read_pos + sign)
+                .is_some_and(u8::is_ascii_digit)
+            {
+                for _ in 0..=sign {
+                    self.read_char();
+                }
+                self.read_digits();
+            }
         }
         self.read_pos -= 1;
         Ok(Token::NumberLiteral(&self.input[pos..self.pos]))
     }
 
+    fn read_digits(&mut self) {
+        while self.ch.is_ascii_digit() {
+            self.read_char();
+        }
+    }
+
     fn peek_char(&self) -> u8 {
         if self.read_pos >= self.input.len() {
             0
@@ -1,12 +1,22 @@
+mod intern;
+mod span;
 mod tokens;
+mod trivia;
+pub use intern::Interner;
+pub use span::Span;
+pub use span::SpannedToken;
+pub use tokens::OwnedToken;
 pub use tokens::Token;
 pub use tokens::TokenDebugInfo;
 pub use tokens::TokenType;
+pub use trivia::Trivia;
+pub use trivia::TriviaToken;
 
 #[allow(clippy::module_inception)]
 mod lexer;
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod test;
 
 pub use lexer::Lexer;
@@ -0,0 +1,39 @@
+use super::Token;
+
+/// A region of the input string. `start` and `end` are byte offsets with `end`
+/// being exclusive, `line` and `col` are where `start` is, both counting from
+/// 1. The default span, with a line of 0, doesn't point at any source.
+#[derive(Hash, PartialEq, Eq, Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end:   usize,
+    pub line:  usize,
+    pub col:   usize,
+}
+impl Span {
+    /// Join this span with one which comes after it, covering both of them and
+    /// everything in between.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: self.end.max(other.end),
+            ..self
+        }
+    }
+
+    /// An empty span at the start of this one, for things like empty blocks
+    /// which don't have any tokens of their own.
+    pub fn empty(self) -> Span {
+        Span {
+            end: self.start,
+            ..self
+        }
+    }
+}
+
+/// A token along with where it is in the input string.
+#[derive(Hash, PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub struct SpannedToken<'a> {
+    pub tok:  Token<'a>,
+    pub span: Span,
+}
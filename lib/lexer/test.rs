@@ -1,5 +1,11 @@
+use std::sync::Arc;
+
+use super::Interner;
 use super::Lexer;
+use super::OwnedToken;
+use super::Span;
 use super::Token;
+use super::Trivia;
 
 #[test]
 fn test_tokenise_integers() {
@@ -26,6 +32,34 @@ fn test_tokenise_integers() {
     }
 }
 
+#[test]
+fn test_tokenise_reals() {
+    let input = "3.14 0.5e3 2E-2 1.x 4e";
+    let expected = [
+        Token::NumberLiteral("3.14"),
+        Token::NumberLiteral("0.5e3"),
+        Token::NumberLiteral("2E-2"),
+        Token::NumberLiteral("1"),
+        Token::Dot,
+        Token::Identifier("x"),
+        Token::NumberLiteral("4"),
+        Token::Identifier("e"),
+        Token::Eof,
+    ];
+    let mut i = 0;
+    let mut lexer = Lexer::new(input);
+
+    loop {
+        let expected_token = &expected[i];
+        let tok = lexer.next_token().unwrap();
+        assert_eq!(tok, *expected_token);
+        if matches!(tok, Token::Eof) {
+            break;
+        }
+        i += 1;
+    }
+}
+
 #[test]
 fn test_tokenise_brackets() {
     let input = "{}[]()";
@@ -54,7 +88,7 @@ fn test_tokenise_brackets() {
 
 #[test]
 fn test_tokenise_symbols() {
-    let input = "+-,/: > == >= <= < !=";
+    let input = "+-,/: > == >= <= < != ^ .";
     let expected = vec![
         Token::Plus,
         Token::Minus,
@@ -67,6 +101,8 @@ fn test_tokenise_symbols() {
         Token::LThanOrEqual,
         Token::LThan,
         Token::NotEqual,
+        Token::Caret,
+        Token::Dot,
         Token::Eof,
     ];
     let mut i = 0;
@@ -85,11 +121,15 @@ fn test_tokenise_symbols() {
 
 #[test]
 fn test_tokenise_keywords() {
-    let input = "global for endfor next while endwhile do until AND if OR
-        NOT endif return function endfunction then switch endswitch case default procedure endprocedure DIV MOD";
+    let input = "global array for to step endfor next while endwhile do until AND if OR
+        NOT endif return function endfunction then switch endswitch case default procedure endprocedure DIV MOD
+        class endclass inherits public private new super byVal byRef else elseif";
     let expected = vec![
         Token::Global,
+        Token::Array,
         Token::For,
+        Token::To,
+        Token::Step,
         Token::Endfor,
         Token::Next,
         Token::While,
@@ -114,6 +154,18 @@ fn test_tokenise_keywords() {
         Token::Endprocedure,
         Token::Div,
         Token::Mod,
+        Token::Newline,
+        Token::Class,
+        Token::Endclass,
+        Token::Inherits,
+        Token::Public,
+        Token::Private,
+        Token::New,
+        Token::Super,
+        Token::ByVal,
+        Token::ByRef,
+        Token::Else,
+        Token::Elseif,
         Token::Eof,
     ];
 
@@ -130,6 +182,46 @@ fn test_tokenise_keywords() {
     }
 }
 
+#[test]
+fn test_tokenise_keywords_any_case() {
+    let input = "IF x and Not y Then ENDIF byval BYREF Endif_";
+    let expected = [
+        Token::If,
+        Token::Identifier("x"),
+        Token::And,
+        Token::Not,
+        Token::Identifier("y"),
+        Token::Then,
+        Token::Endif,
+        Token::ByVal,
+        Token::ByRef,
+        Token::Identifier("Endif_"),
+        Token::Eof,
+    ];
+
+    let mut lexer = Lexer::new(input).with_any_case_keywords();
+    for expected_token in expected {
+        assert_eq!(lexer.next_token().unwrap(), expected_token);
+    }
+}
+
+#[test]
+fn test_tokenise_keywords_case_sensitive() {
+    let input = "Next New and OR";
+    let expected = [
+        Token::Identifier("Next"),
+        Token::Identifier("New"),
+        Token::Identifier("and"),
+        Token::Or,
+        Token::Eof,
+    ];
+
+    let mut lexer = Lexer::new(input);
+    for expected_token in expected {
+        assert_eq!(lexer.next_token().unwrap(), expected_token);
+    }
+}
+
 #[test]
 fn test_tokenise_ignore_comments() {
     let input = "
@@ -220,6 +312,7 @@ fn test_is_infix_op() {
     assert!(Token::GThan.is_infix_op());
     assert!(Token::GThanOrEqual.is_infix_op());
     assert!(Token::LThanOrEqual.is_infix_op());
+    assert!(Token::Caret.is_infix_op());
 
     assert!(!Token::Not.is_infix_op());
     assert!(!Token::Then.is_infix_op());
@@ -252,3 +345,113 @@ fn test_tokenise_booleans() {
         i += 1;
     }
 }
+
+#[test]
+fn test_token_spans() {
+    let input = "x = 10
+  if \"é\" >= y // comment
+endif";
+    let expected = [
+        (Token::Identifier("x"), 0, 1, 1, 1),
+        (Token::Equals, 2, 3, 1, 3),
+        (Token::NumberLiteral("10"), 4, 6, 1, 5),
+        (Token::Newline, 6, 7, 1, 7),
+        (Token::If, 9, 11, 2, 3),
+        (Token::StringLiteral("é"), 12, 16, 2, 6),
+        (Token::GThanOrEqual, 17, 19, 2, 10),
+        (Token::Identifier("y"), 20, 21, 2, 13),
+        (Token::Newline, 32, 33, 2, 25),
+        (Token::Endif, 33, 38, 3, 1),
+        (Token::Eof, 38, 38, 3, 6),
+    ];
+
+    let mut lexer = Lexer::new(input);
+    for (tok, start, end, line, col) in expected {
+        let spanned = lexer.next_spanned_token().unwrap();
+        assert_eq!(spanned.tok, tok);
+        assert_eq!(spanned.span, Span {
+            start,
+            end,
+            line,
+            col,
+        });
+    }
+}
+
+#[test]
+fn test_intern() {
+    let interner = Interner::new();
+    let a = interner.intern("shared");
+    let b = interner.intern(&String::from("shared"));
+    assert_eq!(&*a, "shared");
+    assert!(Arc::ptr_eq(&a, &b));
+    assert!(!Arc::ptr_eq(&a, &interner.intern("other")));
+    assert_eq!(interner.len(), 2);
+
+    // The strings outlive the interner
+    drop(interner);
+    assert_eq!(&*b, "shared");
+}
+
+#[test]
+fn test_token_into_owned() {
+    let interner = Interner::new();
+    let source = String::from("name \"text\" 1.5 endif");
+    let mut lexer = Lexer::new(&source);
+    let mut tokens = Vec::new();
+    loop {
+        let tok = lexer.next_token().unwrap();
+        tokens.push(tok.into_owned(&interner));
+        if tok == Token::Eof {
+            break;
+        }
+    }
+    drop(source);
+    drop(interner);
+    assert_eq!(
+        tokens
+            .iter()
+            .map(OwnedToken::as_token)
+            .collect::<Vec<Token>>(),
+        [
+            Token::Identifier("name"),
+            Token::StringLiteral("text"),
+            Token::NumberLiteral("1.5"),
+            Token::Endif,
+            Token::Eof,
+        ]
+    );
+}
+
+#[test]
+fn test_tokenise_with_trivia() {
+    let input = "  x\t= 1 // one\n\"open";
+    let mut lexer = Lexer::new(input);
+    let mut tokens = vec![];
+    loop {
+        let tok = lexer.next_token_with_trivia();
+        let eof = tok.token.tok == Token::Eof;
+        tokens.push(tok);
+        if eof {
+            break;
+        }
+    }
+    assert_eq!(
+        tokens
+            .iter()
+            .map(|t| (t.leading.clone(), t.text))
+            .collect::<Vec<_>>(),
+        [
+            (vec![Trivia::Whitespace("  ")], "x"),
+            (vec![Trivia::Whitespace("\t")], "="),
+            (vec![Trivia::Whitespace(" ")], "1"),
+            (
+                vec![Trivia::Whitespace(" "), Trivia::Comment("// one")],
+                "\n"
+            ),
+            (vec![], "\"open"),
+            (vec![], ""),
+        ]
+    );
+    assert_eq!(tokens[4].token.tok, Token::Illegal);
+}
@@ -1,7 +1,16 @@
+use std::fmt::Display;
+use std::sync::Arc;
+
+use super::Interner;
+use super::Span;
+use super::SpannedToken;
+
 /// The token emmitted by the lexer. It is worth noting that this is cheap to
 /// copy and move around as it only contains references to data stored in the
 /// input string.
 #[derive(Hash, PartialEq, Eq, Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
 pub enum Token<'a> {
     Identifier(&'a str),
     Equals,
@@ -24,6 +33,7 @@ pub enum Token<'a> {
     Minus,
     Caret,
     Colon,
+    Dot,
 
     /// This is just the literal string of the number, the parser will parse the
     /// number itself later; this is done to allow for cheap copying without
@@ -34,7 +44,10 @@ pub enum Token<'a> {
     Eof,
 
     Global,
+    Array,
     For,
+    To,
+    Step,
     Endfor,
     Next,
     While,
@@ -43,6 +56,7 @@ pub enum Token<'a> {
     Until,
     And,
     If,
+    Elseif,
     Else,
     Or,
     Not,
@@ -57,6 +71,15 @@ pub enum Token<'a> {
     Endswitch,
     Procedure,
     Endprocedure,
+    Class,
+    Endclass,
+    Inherits,
+    Public,
+    Private,
+    New,
+    Super,
+    ByVal,
+    ByRef,
     Div,
     Mod,
 
@@ -69,41 +92,70 @@ pub enum Token<'a> {
     Illegal,
 }
 
+/// Every keyword, along with the token it is read as
+const KEYWORDS: [(&str, Token<'static>); 41] = [
+    ("true", Token::True),
+    ("false", Token::False),
+    ("switch", Token::Switch),
+    ("endswitch", Token::Endswitch),
+    ("case", Token::Case),
+    ("default", Token::Default),
+    ("return", Token::Return),
+    ("for", Token::For),
+    ("to", Token::To),
+    ("step", Token::Step),
+    ("endfor", Token::Endfor),
+    ("global", Token::Global),
+    ("array", Token::Array),
+    ("do", Token::Do),
+    ("until", Token::Until),
+    ("if", Token::If),
+    ("elseif", Token::Elseif),
+    ("else", Token::Else),
+    ("then", Token::Then),
+    ("OR", Token::Or),
+    ("NOT", Token::Not),
+    ("AND", Token::And),
+    ("DIV", Token::Div),
+    ("MOD", Token::Mod),
+    ("while", Token::While),
+    ("endwhile", Token::Endwhile),
+    ("next", Token::Next),
+    ("endif", Token::Endif),
+    ("procedure", Token::Procedure),
+    ("endprocedure", Token::Endprocedure),
+    ("function", Token::Function),
+    ("endfunction", Token::Endfunction),
+    ("class", Token::Class),
+    ("endclass", Token::Endclass),
+    ("inherits", Token::Inherits),
+    ("public", Token::Public),
+    ("private", Token::Private),
+    ("new", Token::New),
+    ("super", Token::Super),
+    ("byVal", Token::ByVal),
+    ("byRef", Token::ByRef),
+];
+
 /// Check the identifier against a map of keywords, if none of them match then
 /// Token::Identifier will be returned.
-pub fn lookup_keyword(ident: &str) -> Token {
-    use Token::*;
+pub fn lookup_keyword(ident: &str) -> Token<'_> {
+    match KEYWORDS.iter().find(|(keyword, _)| *keyword == ident) {
+        Some((_, tok)) => *tok,
+        None => Token::Identifier(ident),
+    }
+}
 
-    match ident {
-        "true" => True,
-        "false" => False,
-        "switch" => Switch,
-        "endswitch" => Endswitch,
-        "case" => Case,
-        "default" => Default,
-        "return" => Return,
-        "for" => For,
-        "endfor" => Endfor,
-        "global" => Global,
-        "do" => Do,
-        "until" => Until,
-        "if" => If,
-        "else" => Else,
-        "then" => Then,
-        "OR" => Or,
-        "NOT" => Not,
-        "AND" => And,
-        "DIV" => Div,
-        "MOD" => Mod,
-        "while" => While,
-        "endwhile" => Endwhile,
-        "next" => Next,
-        "endif" => Endif,
-        "procedure" => Procedure,
-        "endprocedure" => Endprocedure,
-        "function" => Function,
-        "endfunction" => Endfunction,
-        _ => Identifier(ident),
+/// Like `lookup_keyword`, but keywords written in any case are matched too, so
+/// `ENDIF` and `and` are keywords. The language is case sensitive, this is
+/// only for tools which tidy up code.
+pub fn lookup_keyword_any_case(ident: &str) -> Token<'_> {
+    match KEYWORDS
+        .iter()
+        .find(|(keyword, _)| keyword.eq_ignore_ascii_case(ident))
+    {
+        Some((_, tok)) => *tok,
+        None => Token::Identifier(ident),
     }
 }
 
@@ -114,7 +166,19 @@ impl Token<'_> {
         use Token::*;
         matches!(
             self,
-            Endif | Endfunction | Endprocedure | Endfor | Endwhile | Endswitch | Else
+            Endif
+                | Endfunction
+                | Endprocedure
+                | Endfor
+                | Next
+                | Endwhile
+                | Until
+                | Case
+                | Default
+                | Endswitch
+                | Elseif
+                | Else
+                | Endclass
         )
     }
 
@@ -136,20 +200,217 @@ impl Token<'_> {
                 | Self::GThan
                 | Self::GThanOrEqual
                 | Self::FSlash
+                | Self::Caret
                 | Self::Or
                 | Self::And
         )
     }
+
+    /// The same token, but owning any text it holds so that it doesn't borrow
+    /// from the input string. The text is interned.
+    pub fn into_owned(self, interner: &Interner) -> OwnedToken {
+        use Token::*;
+
+        let tok = match self {
+            Identifier(s) => return OwnedToken::Identifier(interner.intern(s)),
+            Equals => Equals,
+            DoubleEquals => DoubleEquals,
+            NotEqual => NotEqual,
+            GThanOrEqual => GThanOrEqual,
+            LThanOrEqual => LThanOrEqual,
+            GThan => GThan,
+            LThan => LThan,
+            StringLiteral(s) => return OwnedToken::StringLiteral(interner.intern(s)),
+            LParenthasis => LParenthasis,
+            RParenthasis => RParenthasis,
+            LSquirly => LSquirly,
+            RSquirly => RSquirly,
+            LSquareBracket => LSquareBracket,
+            RSquareBracket => RSquareBracket,
+            Plus => Plus,
+            Asterisk => Asterisk,
+            FSlash => FSlash,
+            Minus => Minus,
+            Caret => Caret,
+            Colon => Colon,
+            Dot => Dot,
+            NumberLiteral(s) => return OwnedToken::NumberLiteral(interner.intern(s)),
+            Comma => Comma,
+            Eof => Eof,
+            Global => Global,
+            Array => Array,
+            For => For,
+            To => To,
+            Step => Step,
+            Endfor => Endfor,
+            Next => Next,
+            While => While,
+            Endwhile => Endwhile,
+            Do => Do,
+            Until => Until,
+            And => And,
+            If => If,
+            Elseif => Elseif,
+            Else => Else,
+            Or => Or,
+            Not => Not,
+            Endif => Endif,
+            Return => Return,
+            Function => Function,
+            Endfunction => Endfunction,
+            Then => Then,
+            Switch => Switch,
+            Case => Case,
+            Default => Default,
+            Endswitch => Endswitch,
+            Procedure => Procedure,
+            Endprocedure => Endprocedure,
+            Class => Class,
+            Endclass => Endclass,
+            Inherits => Inherits,
+            Public => Public,
+            Private => Private,
+            New => New,
+            Super => Super,
+            ByVal => ByVal,
+            ByRef => ByRef,
+            Div => Div,
+            Mod => Mod,
+            True => True,
+            False => False,
+            Newline => Newline,
+            Illegal => Illegal,
+        };
+        OwnedToken::Other(tok)
+    }
+}
+
+/// A token which owns its text, made with `Token::into_owned`. Being free of
+/// the input's lifetime, it can be kept around and sent between threads.
+#[derive(Hash, PartialEq, Eq, Debug, Clone)]
+pub enum OwnedToken {
+    Identifier(Arc<str>),
+    StringLiteral(Arc<str>),
+    NumberLiteral(Arc<str>),
+
+    /// Any other token, none of which hold text
+    Other(Token<'static>),
+}
+impl OwnedToken {
+    /// The token borrowing its text from this one
+    pub fn as_token(&self) -> Token<'_> {
+        match self {
+            Self::Identifier(s) => Token::Identifier(s),
+            Self::StringLiteral(s) => Token::StringLiteral(s),
+            Self::NumberLiteral(s) => Token::NumberLiteral(s),
+            Self::Other(tok) => *tok,
+        }
+    }
+}
+
+/// Tokens are deserialized from the same `{"kind": .., "value": ..}` form they
+/// are serialized to. Any text is borrowed from the input, the same as when
+/// lexing.
+#[cfg(feature = "serde")]
+impl<'de: 'a, 'a> serde::Deserialize<'de> for Token<'a> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Tagged<'a> {
+            kind:  TokenType,
+            #[serde(borrow)]
+            value: Option<&'a str>,
+        }
+
+        let tagged = Tagged::deserialize(deserializer)?;
+        let text = || match tagged.value {
+            Some(value) => Ok(value),
+            None => Err(<D::Error as serde::de::Error>::missing_field("value")),
+        };
+
+        Ok(match tagged.kind {
+            TokenType::Identifier => Token::Identifier(text()?),
+            TokenType::Equals => Token::Equals,
+            TokenType::DoubleEquals => Token::DoubleEquals,
+            TokenType::NotEqual => Token::NotEqual,
+            TokenType::GThanOrEqual => Token::GThanOrEqual,
+            TokenType::LThanOrEqual => Token::LThanOrEqual,
+            TokenType::GThan => Token::GThan,
+            TokenType::LThan => Token::LThan,
+            TokenType::StringLiteral => Token::StringLiteral(text()?),
+            TokenType::LParenthasis => Token::LParenthasis,
+            TokenType::RParenthasis => Token::RParenthasis,
+            TokenType::LSquirly => Token::LSquirly,
+            TokenType::RSquirly => Token::RSquirly,
+            TokenType::LSquareBracket => Token::LSquareBracket,
+            TokenType::RSquareBracket => Token::RSquareBracket,
+            TokenType::Plus => Token::Plus,
+            TokenType::Asterisk => Token::Asterisk,
+            TokenType::FSlash => Token::FSlash,
+            TokenType::Minus => Token::Minus,
+            TokenType::Caret => Token::Caret,
+            TokenType::Colon => Token::Colon,
+            TokenType::Dot => Token::Dot,
+            TokenType::NumberLiteral => Token::NumberLiteral(text()?),
+            TokenType::Comma => Token::Comma,
+            TokenType::Eof => Token::Eof,
+            TokenType::Global => Token::Global,
+            TokenType::Array => Token::Array,
+            TokenType::For => Token::For,
+            TokenType::To => Token::To,
+            TokenType::Step => Token::Step,
+            TokenType::Endfor => Token::Endfor,
+            TokenType::Next => Token::Next,
+            TokenType::While => Token::While,
+            TokenType::Endwhile => Token::Endwhile,
+            TokenType::Do => Token::Do,
+            TokenType::Until => Token::Until,
+            TokenType::And => Token::And,
+            TokenType::If => Token::If,
+            TokenType::Elseif => Token::Elseif,
+            TokenType::Else => Token::Else,
+            TokenType::Or => Token::Or,
+            TokenType::Not => Token::Not,
+            TokenType::Endif => Token::Endif,
+            TokenType::Return => Token::Return,
+            TokenType::Function => Token::Function,
+            TokenType::Endfunction => Token::Endfunction,
+            TokenType::Then => Token::Then,
+            TokenType::Switch => Token::Switch,
+            TokenType::Case => Token::Case,
+            TokenType::Default => Token::Default,
+            TokenType::Endswitch => Token::Endswitch,
+            TokenType::Procedure => Token::Procedure,
+            TokenType::Endprocedure => Token::Endprocedure,
+            TokenType::Class => Token::Class,
+            TokenType::Endclass => Token::Endclass,
+            TokenType::Inherits => Token::Inherits,
+            TokenType::Public => Token::Public,
+            TokenType::Private => Token::Private,
+            TokenType::New => Token::New,
+            TokenType::Super => Token::Super,
+            TokenType::ByVal => Token::ByVal,
+            TokenType::ByRef => Token::ByRef,
+            TokenType::Div => Token::Div,
+            TokenType::Mod => Token::Mod,
+            TokenType::True => Token::True,
+            TokenType::False => Token::False,
+            TokenType::Newline => Token::Newline,
+            TokenType::Illegal => Token::Illegal,
+        })
+    }
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TokenDebugInfo {
     pub tok_type: TokenType,
+    pub span:     Span,
 }
-impl From<Token<'_>> for TokenDebugInfo {
-    fn from(value: Token) -> Self {
+impl From<SpannedToken<'_>> for TokenDebugInfo {
+    fn from(value: SpannedToken) -> Self {
         Self {
-            tok_type: value.into(),
+            tok_type: value.tok.into(),
+            span:     value.span,
         }
     }
 }
@@ -157,6 +418,7 @@ impl From<Token<'_>> for TokenDebugInfo {
 // Variation of the Token enum except with no attached data, only the variant
 // type data is stored
 #[derive(Hash, PartialEq, Eq, Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenType {
     Identifier,
     Equals,
@@ -179,12 +441,16 @@ pub enum TokenType {
     Minus,
     Caret,
     Colon,
+    Dot,
     NumberLiteral,
     Comma,
     Eof,
 
     Global,
+    Array,
     For,
+    To,
+    Step,
     Endfor,
     Next,
     While,
@@ -193,6 +459,7 @@ pub enum TokenType {
     Until,
     And,
     If,
+    Elseif,
     Else,
     Or,
     Not,
@@ -207,6 +474,15 @@ pub enum TokenType {
     Endswitch,
     Procedure,
     Endprocedure,
+    Class,
+    Endclass,
+    Inherits,
+    Public,
+    Private,
+    New,
+    Super,
+    ByVal,
+    ByRef,
     Div,
     Mod,
 
@@ -243,11 +519,15 @@ impl From<Token<'_>> for TokenType {
             Token::Minus => Minus,
             Token::Caret => Caret,
             Token::Colon => Colon,
+            Token::Dot => Dot,
             Token::NumberLiteral(_) => NumberLiteral,
             Token::Comma => Comma,
             Token::Eof => Eof,
             Token::Global => Global,
+            Token::Array => Array,
             Token::For => For,
+            Token::To => To,
+            Token::Step => Step,
             Token::Endfor => Endfor,
             Token::Next => Next,
             Token::While => While,
@@ -256,6 +536,7 @@ impl From<Token<'_>> for TokenType {
             Token::Until => Until,
             Token::And => And,
             Token::If => If,
+            Token::Elseif => Elseif,
             Token::Else => Else,
             Token::Or => Or,
             Token::Not => Not,
@@ -270,6 +551,15 @@ impl From<Token<'_>> for TokenType {
             Token::Endswitch => Endswitch,
             Token::Procedure => Procedure,
             Token::Endprocedure => Endprocedure,
+            Token::Class => Class,
+            Token::Endclass => Endclass,
+            Token::Inherits => Inherits,
+            Token::Public => Public,
+            Token::Private => Private,
+            Token::New => New,
+            Token::Super => Super,
+            Token::ByVal => ByVal,
+            Token::ByRef => ByRef,
             Token::Div => Div,
             Token::Mod => Mod,
             Token::True => True,
@@ -279,3 +569,81 @@ impl From<Token<'_>> for TokenType {
         }
     }
 }
+impl Display for TokenType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use TokenType::*;
+
+        write!(f, "{}", match self {
+            Identifier => "identifier",
+            StringLiteral => "string literal",
+            NumberLiteral => "number",
+            Newline => "newline",
+            Eof => "end of file",
+            Illegal => "illegal character",
+
+            Equals => "`=`",
+            DoubleEquals => "`==`",
+            NotEqual => "`!=`",
+            GThanOrEqual => "`>=`",
+            LThanOrEqual => "`<=`",
+            GThan => "`>`",
+            LThan => "`<`",
+            LParenthasis => "`(`",
+            RParenthasis => "`)`",
+            LSquirly => "`{`",
+            RSquirly => "`}`",
+            LSquareBracket => "`[`",
+            RSquareBracket => "`]`",
+            Plus => "`+`",
+            Asterisk => "`*`",
+            FSlash => "`/`",
+            Minus => "`-`",
+            Caret => "`^`",
+            Colon => "`:`",
+            Dot => "`.`",
+            Comma => "`,`",
+
+            Global => "`global`",
+            Array => "`array`",
+            For => "`for`",
+            To => "`to`",
+            Step => "`step`",
+            Endfor => "`endfor`",
+            Next => "`next`",
+            While => "`while`",
+            Endwhile => "`endwhile`",
+            Do => "`do`",
+            Until => "`until`",
+            And => "`AND`",
+            If => "`if`",
+            Elseif => "`elseif`",
+            Else => "`else`",
+            Or => "`OR`",
+            Not => "`NOT`",
+            Endif => "`endif`",
+            Return => "`return`",
+            Function => "`function`",
+            Endfunction => "`endfunction`",
+            Then => "`then`",
+            Switch => "`switch`",
+            Case => "`case`",
+            Default => "`default`",
+            Endswitch => "`endswitch`",
+            Procedure => "`procedure`",
+            Endprocedure => "`endprocedure`",
+            Class => "`class`",
+            Endclass => "`endclass`",
+            Inherits => "`inherits`",
+            Public => "`public`",
+            Private => "`private`",
+            New => "`new`",
+            Super => "`super`",
+            ByVal => "`byVal`",
+            ByRef => "`byRef`",
+            Div => "`DIV`",
+            Mod => "`MOD`",
+            True => "`true`",
+            False => "`false`",
+        })
+    }
+}
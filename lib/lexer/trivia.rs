@@ -0,0 +1,60 @@
+use std::fmt::Display;
+
+use super::SpannedToken;
+
+/// Text between tokens which the parser doesn't care about
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Trivia<'a> {
+    /// Spaces and tabs, newlines are tokens of their own
+    Whitespace(&'a str),
+    /// A `//` comment, up to but not including the newline
+    Comment(&'a str),
+}
+impl<'a> Trivia<'a> {
+    pub fn text(&self) -> &'a str {
+        match self {
+            Self::Whitespace(s) | Self::Comment(s) => s,
+        }
+    }
+
+    /// Split the text between two tokens into whitespace and comments
+    pub(super) fn split(mut text: &'a str) -> Vec<Trivia<'a>> {
+        let mut trivia = vec![];
+        while !text.is_empty() {
+            let (piece, rest) = if text.starts_with("//") {
+                text.split_at(text.find('\n').unwrap_or(text.len()))
+            } else {
+                text.split_at(text.find("//").unwrap_or(text.len()))
+            };
+            trivia.push(if piece.starts_with("//") {
+                Self::Comment(piece)
+            } else {
+                Self::Whitespace(piece)
+            });
+            text = rest;
+        }
+        trivia
+    }
+}
+impl Display for Trivia<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+/// A token along with the trivia before it and its exact text, so that the
+/// input can be put back together from the tokens
+#[derive(Hash, PartialEq, Eq, Debug, Clone)]
+pub struct TriviaToken<'a> {
+    pub leading: Vec<Trivia<'a>>,
+    pub token:   SpannedToken<'a>,
+    pub text:    &'a str,
+}
+impl Display for TriviaToken<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for t in &self.leading {
+            write!(f, "{}", t)?;
+        }
+        write!(f, "{}", self.text)
+    }
+}
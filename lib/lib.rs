@@ -1,4 +1,16 @@
 #![feature(let_chains)]
+#![allow(stable_features)]
+pub mod cst;
+
+pub mod diagnostics;
+
+pub mod format;
+
+pub mod interpreter;
+
+#[cfg(feature = "serde")]
+pub mod json;
+
 pub mod lexer;
 
 pub mod parser;
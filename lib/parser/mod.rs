@@ -2,7 +2,12 @@
 mod parser;
 
 pub use parser::parse_from_lexer;
+pub use parser::parse_from_lexer_with_recovery;
 pub use parser::parse_from_string;
+pub use parser::parse_with_recovery;
+pub use parser::ParserError;
+pub(crate) use parser::Precedence;
+pub use parser::Program;
 
 #[cfg(test)]
 mod test;
@@ -1,35 +1,63 @@
+use std::fmt::Display;
+
 use crate::lexer::Lexer;
 use crate::lexer::LexerError;
+use crate::lexer::Span;
+use crate::lexer::SpannedToken;
 use crate::lexer::Token;
 use crate::lexer::TokenDebugInfo;
+use crate::lexer::TokenType;
+use crate::syntax::ArrayDeclarationStatement;
 use crate::syntax::AssignStatement;
+use crate::syntax::AssignTarget;
+use crate::syntax::AstNode;
 use crate::syntax::BlockStatement;
 use crate::syntax::BooleanExpression;
-use crate::syntax::Expression;
+use crate::syntax::ClassAttribute;
+use crate::syntax::ClassMethod;
+use crate::syntax::ClassStatement;
+use crate::syntax::DoUntilStatement;
+use crate::syntax::ErrorStatement;
+use crate::syntax::Expr;
 use crate::syntax::ExpressionStatement;
-use crate::syntax::ExpressionType;
+use crate::syntax::ForStatement;
 use crate::syntax::FunctionCallExpression;
 use crate::syntax::FunctionStatement;
 use crate::syntax::Identifier;
 use crate::syntax::IfStatement;
+use crate::syntax::IndexExpression;
 use crate::syntax::InfixExpression;
+use crate::syntax::InfixOperator;
 use crate::syntax::IntegerLiteralExpression;
+use crate::syntax::MemberAccessExpression;
+use crate::syntax::MethodCallExpression;
+use crate::syntax::NewExpression;
 use crate::syntax::NoSuchInfixOperatorError;
+use crate::syntax::Parameter;
+use crate::syntax::PassBy;
 use crate::syntax::PrefixExpression;
+use crate::syntax::RealLiteralExpression;
 use crate::syntax::ReturnStatement;
-use crate::syntax::Statement;
+use crate::syntax::Stmt;
+use crate::syntax::StringLiteralExpression;
+use crate::syntax::SuperExpression;
+use crate::syntax::SwitchCase;
+use crate::syntax::SwitchStatement;
+use crate::syntax::Visibility;
+use crate::syntax::WhileStatement;
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Precedence {
     #[default]
     Lowest,
+    Or,
+    And,
     Equality,
     Inequality,
-    And,
-    Or,
     Sum,
     Product,
     Prefix,
+    Power,
     Call,
 }
 impl From<Token<'_>> for Precedence {
@@ -41,62 +69,146 @@ impl From<Token<'_>> for Precedence {
             DoubleEquals | NotEqual => Self::Equality,
             Plus | Minus => Self::Sum,
             FSlash | Asterisk | Mod | Div => Self::Product,
+            Caret => Self::Power,
             And => Self::And,
             Or => Self::Or,
-            LParenthasis => Self::Call,
+            LParenthasis | LSquareBracket | Dot => Self::Call,
             _ => Self::Lowest,
         }
     }
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
 pub enum ParserError {
-    UnterminatedStringLiteral,
-    InvalidNumberLiteral,
-    TooLargeInteger,
+    UnterminatedStringLiteral(Span),
+    InvalidNumberLiteral(Span),
+    TooLargeInteger(Span),
+
+    /// The identifier after `next` isn't the counter of the loop it closes
+    MismatchedLoopCounter(Span),
 
     UnexpectedToken(TokenDebugInfo),
+    ExpectedToken(TokenType, TokenDebugInfo),
+    ExpectedExpression(TokenDebugInfo),
+}
+impl ParserError {
+    /// Where in the source code the error is
+    pub fn span(&self) -> Span {
+        match self {
+            Self::UnterminatedStringLiteral(s)
+            | Self::InvalidNumberLiteral(s)
+            | Self::TooLargeInteger(s)
+            | Self::MismatchedLoopCounter(s) => *s,
+            Self::UnexpectedToken(t) | Self::ExpectedToken(_, t) | Self::ExpectedExpression(t) => {
+                t.span
+            }
+        }
+    }
+}
+impl Display for ParserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnterminatedStringLiteral(_) => write!(f, "unterminated string literal"),
+            Self::InvalidNumberLiteral(_) => write!(f, "invalid number literal"),
+            Self::TooLargeInteger(_) => write!(f, "integer literal is too large"),
+            Self::MismatchedLoopCounter(_) => {
+                write!(
+                    f,
+                    "the identifier after `next` doesn't match the loop counter"
+                )
+            }
+            Self::UnexpectedToken(t) => write!(f, "unexpected {}", t.tok_type),
+            Self::ExpectedToken(expected, t) => {
+                write!(f, "expected {}, found {}", expected, t.tok_type)
+            }
+            Self::ExpectedExpression(t) => {
+                write!(f, "expected an expression, found {}", t.tok_type)
+            }
+        }
+    }
 }
 impl From<LexerError> for ParserError {
     fn from(value: LexerError) -> Self {
         match value {
-            LexerError::UnterminatedStringLiteral => Self::UnterminatedStringLiteral,
+            LexerError::UnterminatedStringLiteral(s) => Self::UnterminatedStringLiteral(s),
         }
     }
 }
-impl From<NoSuchInfixOperatorError<'_>> for ParserError {
-    fn from(value: NoSuchInfixOperatorError) -> ParserError {
-        ParserError::UnexpectedToken(value.tok.into())
-    }
-}
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct Program<'a> {
-    pub statements: Vec<Box<dyn Statement + 'a>>,
+    pub statements: Vec<Stmt<'a>>,
 }
 
 #[derive(Default, Debug)]
 struct Parser<'a> {
-    lexer:    Lexer<'a>,
-    tok:      Token<'a>,
-    peek_tok: Token<'a>,
-    pub prog: Program<'a>,
+    lexer:     Lexer<'a>,
+    tok:       Token<'a>,
+    tok_span:  Span,
+    peek_tok:  Token<'a>,
+    peek_span: Span,
+    pub prog:  Program<'a>,
+
+    /// The last token passed over which wasn't a newline, where an
+    /// `ErrorStatement` ends
+    last_span: Span,
+
+    /// When set, errors are collected in `errors` and replaced with an
+    /// `ErrorStatement` instead of stopping the parse
+    recovering: bool,
+    errors:     Vec<ParserError>,
 }
 
 impl<'a> Parser<'a> {
     pub fn parse(&mut self) -> Result<(), ParserError> {
+        while let Some(s) = self.parse_statement()? {
+            self.prog.statements.push(s);
+            self.next_token()?;
+        }
+        Ok(())
+    }
+
+    /// Like `parse`, but carries on after each error so that every error in
+    /// the input is found, see `recover`.
+    pub fn parse_recovering(&mut self) -> Result<(), ParserError> {
         loop {
-            match self.parse_statement()? {
-                Some(s) => self.prog.statements.push(s),
-                None => break,
+            self.skip_newlines()?;
+            let start = self.tok_span;
+            match self.parse_statement() {
+                Ok(Some(s)) => self.prog.statements.push(s),
+                Ok(None) => break,
+                // At the top level any block ender the parser stops at is a stray one, which
+                // is skipped by the `next_token` below
+                Err(e) => {
+                    let err = self.recover(e, start)?;
+                    self.prog.statements.push(err);
+                }
             }
             self.next_token()?;
         }
         Ok(())
     }
 
+    /// Record the error and skip to a token the parser can carry on from: the
+    /// end of the line, the end of the block or the end of the input. Gives
+    /// back an `ErrorStatement` to stand in for whatever failed to parse, which
+    /// covers everything from `start`, where the statement began, up to there.
+    fn recover(&mut self, err: ParserError, start: Span) -> Result<Stmt<'a>, ParserError> {
+        self.errors.push(err);
+        while !(self.tok.is_block_ender() || matches!(self.tok, Token::Newline | Token::Eof)) {
+            self.next_token()?;
+        }
+        Ok(Stmt::Error(ErrorStatement {
+            span: start.to(self.last_span),
+        }))
+    }
+
     /// Returns Ok(None) only in the case of Token::Eof
-    fn parse_statement(&mut self) -> Result<Option<Box<dyn Statement + 'a>>, ParserError> {
+    fn parse_statement(&mut self) -> Result<Option<Stmt<'a>>, ParserError> {
         loop {
             match () {
                 () if matches!(self.tok, Token::Newline) => (),
@@ -105,104 +217,165 @@ impl<'a> Parser<'a> {
                 // Return statements
                 () if matches!(self.tok, Token::Return) => {
                     let return_stmt = self.parse_return_statement()?;
-                    return Ok(Some(Box::new(return_stmt)));
+                    return Ok(Some(Stmt::Return(return_stmt)));
                 }
 
                 // If statements
                 () if matches!(self.tok, Token::If) => {
                     let if_stmt = self.parse_if_statement()?;
-                    return Ok(Some(Box::new(if_stmt)));
+                    return Ok(Some(Stmt::If(if_stmt)));
+                }
+
+                // For loops
+                () if matches!(self.tok, Token::For) => {
+                    let for_stmt = self.parse_for_statement()?;
+                    return Ok(Some(Stmt::For(for_stmt)));
+                }
+
+                // While loops
+                () if matches!(self.tok, Token::While) => {
+                    let while_stmt = self.parse_while_statement()?;
+                    return Ok(Some(Stmt::While(while_stmt)));
+                }
+
+                // Do until loops
+                () if matches!(self.tok, Token::Do) => {
+                    let do_until_stmt = self.parse_do_until_statement()?;
+                    return Ok(Some(Stmt::DoUntil(do_until_stmt)));
+                }
+
+                // Switch statements
+                () if matches!(self.tok, Token::Switch) => {
+                    let switch_stmt = self.parse_switch_statement()?;
+                    return Ok(Some(Stmt::Switch(switch_stmt)));
                 }
 
                 // Function/procedure declaration
                 () if matches!(self.tok, Token::Function)
                     || matches!(self.tok, Token::Procedure) =>
                 {
-                    let func = self.parse_function()?;
-                    return Ok(Some(Box::new(func)));
+                    let func = self.parse_function(false)?;
+                    return Ok(Some(Stmt::Function(func)));
+                }
+
+                // Class declaration
+                () if matches!(self.tok, Token::Class) => {
+                    let class = self.parse_class()?;
+                    return Ok(Some(Stmt::Class(class)));
+                }
+
+                // Array declarations
+                () if matches!(self.tok, Token::Array) => {
+                    let array_stmt = self.parse_array_declaration()?;
+                    return Ok(Some(Stmt::ArrayDeclaration(array_stmt)));
                 }
 
                 // Assign statements
                 () if (matches!(self.tok, Token::Global)
                     || (matches!(self.tok, Token::Identifier(_)))
-                        && matches!(self.peek_tok, Token::Equals)) =>
+                        && (matches!(self.peek_tok, Token::Equals)
+                            || self.is_index_assignment()
+                            || self.is_member_assignment())) =>
                 {
-                    dbg!("parsing assign at", self.tok);
                     let assign_stmt = self.parse_assign_statement()?;
-                    dbg!(&assign_stmt);
-                    return Ok(Some(Box::new(assign_stmt)));
+                    return Ok(Some(Stmt::Assign(assign_stmt)));
                 }
 
                 // Expression statements
                 _ => {
                     let exp = self.parse_expr(Precedence::Lowest)?;
-                    return Ok(Some(Box::new(ExpressionStatement { value: exp })));
+                    return Ok(Some(Stmt::Expression(ExpressionStatement {
+                        span:  exp.span(),
+                        value: Box::new(exp),
+                    })));
                 }
             }
             self.next_token()?;
         }
     }
 
-    fn parse_expr(&mut self, prec: Precedence) -> Result<Box<dyn Expression + 'a>, ParserError> {
-        let ident = match self.tok {
-            Token::Identifier(_) => Some(self.tok.into()),
+    fn parse_expr(&mut self, prec: Precedence) -> Result<Expr<'a>, ParserError> {
+        // Only a bare identifier can be called, so it is only given for the
+        // first infix step. Anything else, like `a[0](1)`, is an error.
+        let mut ident = match self.tok {
+            Token::Identifier(_) => Some(self.spanned().into()),
             _ => None,
         };
         let mut left_expr = self.parse_left_expr()?;
         while !matches!(self.peek_tok, Token::Newline | Token::Eof) && prec < self.peek_tok.into() {
             self.next_token()?;
-            left_expr = self.parse_infix_expression(left_expr, ident.clone())?;
+            left_expr = self.parse_infix_expression(left_expr, ident.take())?;
         }
         Ok(left_expr)
     }
 
     fn parse_infix_expression(
         &mut self,
-        left: Box<dyn Expression + 'a>,
+        left: Expr<'a>,
         ident: Option<Identifier<'a>>,
-    ) -> Result<Box<dyn Expression + 'a>, ParserError> {
+    ) -> Result<Expr<'a>, ParserError> {
         if self.tok == Token::LParenthasis {
             match ident {
-                Some(i) => {
-                    Ok(Box::new(self.parse_function_call(i)?))
-                }
-                None => Err(ParserError::UnexpectedToken(self.tok.into())),
+                Some(i) => Ok(Expr::FunctionCall(self.parse_function_call(i)?)),
+                None => Err(self.unexpected_token()),
             }
+        } else if self.tok == Token::LSquareBracket {
+            Ok(Expr::Index(self.parse_index_expression(left)?))
+        } else if self.tok == Token::Dot {
+            self.parse_member_expression(left)
         } else {
-            Ok(Box::new(InfixExpression {
-                left,
-                token: self.tok,
-                operator: self.tok.try_into()?,
-                right: {
-                    let prec: Precedence = self.tok.into();
-                    self.next_token()?;
-                    self.parse_expr(prec)?
-                }
-            },
-            ))
-    }
+            let token = self.tok;
+            let operator = self
+                .tok
+                .try_into()
+                .map_err(|_: NoSuchInfixOperatorError| self.unexpected_token())?;
+            // `^` is right associative, so the right hand side is parsed one level
+            // lower to let it take in any more `^`s
+            let prec = match operator {
+                InfixOperator::Power => Precedence::Prefix,
+                _ => self.tok.into(),
+            };
+            self.next_token()?;
+            let right = self.parse_expr(prec)?;
+            Ok(Expr::Infix(InfixExpression {
+                span: left.span().to(right.span()),
+                left: Box::new(left),
+                token,
+                operator,
+                right: Box::new(right),
+            }))
+        }
     }
 
-    fn parse_left_expr(&mut self) -> Result<Box<dyn Expression + 'a>, ParserError> {
+    fn parse_left_expr(&mut self) -> Result<Expr<'a>, ParserError> {
         use Token::*;
 
         match self.tok {
-            Not | Plus | Minus => Ok(Box::new(self.parse_prefix_expr()?)),
-            Identifier(_) => Ok(Box::new(self.parse_identifier()?)),
-            NumberLiteral(_) => Ok(Box::new(self.parse_number_literal_expr()?)),
-            True | False => Ok(Box::new(self.parse_bool_expr()?)),
-            LParenthasis => Ok(self.parse_grouped_expr()?),
-            _ => Err(ParserError::UnexpectedToken(self.tok.into())),
+            Not | Plus | Minus => Ok(Expr::Prefix(self.parse_prefix_expr()?)),
+            Identifier(_) => Ok(Expr::Identifier(self.parse_identifier()?)),
+            NumberLiteral(n) if n.contains(['.', 'e', 'E']) => {
+                Ok(Expr::RealLiteral(self.parse_real_literal_expr()?))
+            }
+            NumberLiteral(_) => Ok(Expr::IntegerLiteral(self.parse_number_literal_expr()?)),
+            StringLiteral(_) => Ok(Expr::StringLiteral(self.parse_string_literal_expr()?)),
+            True | False => Ok(Expr::Boolean(self.parse_bool_expr()?)),
+            LParenthasis => self.parse_grouped_expr(),
+            New => Ok(Expr::New(self.parse_new_expr()?)),
+            Super => Ok(Expr::Super(SuperExpression {
+                token: self.tok,
+                span:  self.tok_span,
+            })),
+            _ => Err(self.expected_expression()),
         }
     }
 
-    fn parse_grouped_expr(&mut self) -> Result<Box<dyn Expression + 'a>, ParserError> {
+    fn parse_grouped_expr(&mut self) -> Result<Expr<'a>, ParserError> {
         self.next_token()?;
         let expr = self.parse_expr(Precedence::Lowest)?;
         self.next_token()?;
         match self.tok {
             Token::RParenthasis => Ok(expr),
-            _ => Err(ParserError::UnexpectedToken(self.tok.into())),
+            _ => Err(self.expected(TokenType::RParenthasis)),
         }
     }
 
@@ -210,14 +383,16 @@ impl<'a> Parser<'a> {
         &mut self,
         identifier: Identifier<'a>,
     ) -> Result<FunctionCallExpression<'a>, ParserError> {
+        let args = self.parse_call_args()?;
         Ok(FunctionCallExpression {
             token: identifier.token,
-            func:  identifier.token.into(),
-            args:  self.parse_call_args()?,
+            span: identifier.span.to(self.tok_span),
+            func: identifier,
+            args,
         })
     }
 
-    fn parse_call_args(&mut self) -> Result<Vec<Box<dyn Expression + 'a>>, ParserError> {
+    fn parse_call_args(&mut self) -> Result<Vec<Expr<'a>>, ParserError> {
         let mut args = Vec::new();
         // The current token should be the LParenthasis
         self.next_token()?;
@@ -235,42 +410,139 @@ impl<'a> Parser<'a> {
             self.next_token()?;
         }
         if self.tok != Token::RParenthasis {
-            return Err(ParserError::UnexpectedToken(self.tok.into()));
+            return Err(self.expected(TokenType::RParenthasis));
         }
 
         Ok(args)
     }
 
+    fn parse_new_expr(&mut self) -> Result<NewExpression<'a>, ParserError> {
+        let token = self.tok;
+        let start = self.tok_span;
+        self.next_token()?;
+        let class = self.parse_identifier()?;
+        self.next_token()?;
+        if self.tok != Token::LParenthasis {
+            return Err(self.expected(TokenType::LParenthasis));
+        }
+        let args = self.parse_call_args()?;
+        Ok(NewExpression {
+            token,
+            span: start.to(self.tok_span),
+            class,
+            args,
+        })
+    }
+
+    /// Parse the `.member` or `.method(args)` after the subject, the current
+    /// token should be the `.`
+    fn parse_member_expression(&mut self, subject: Expr<'a>) -> Result<Expr<'a>, ParserError> {
+        let token = self.tok;
+        self.next_token()?;
+        let member = self.parse_member_name()?;
+        if self.peek_tok != Token::LParenthasis {
+            return Ok(Expr::MemberAccess(MemberAccessExpression {
+                token,
+                span: subject.span().to(member.span),
+                subject: Box::new(subject),
+                member,
+            }));
+        }
+        self.next_token()?;
+        let args = self.parse_call_args()?;
+        Ok(Expr::MethodCall(MethodCallExpression {
+            token,
+            span: subject.span().to(self.tok_span),
+            subject: Box::new(subject),
+            method: member,
+            args,
+        }))
+    }
+
+    /// The name after a `.`, which can be `new` for calling a constructor with
+    /// `super.new()`
+    fn parse_member_name(&mut self) -> Result<Identifier<'a>, ParserError> {
+        match self.tok {
+            Token::New => Ok(self.constructor_ident()),
+            _ => self.parse_identifier(),
+        }
+    }
+
+    /// `new` is a keyword, but it's also the name of every constructor
+    fn constructor_ident(&self) -> Identifier<'a> {
+        Identifier {
+            token: Token::Identifier("new"),
+            span:  self.tok_span,
+        }
+    }
+
+    fn parse_index_expression(
+        &mut self,
+        subject: Expr<'a>,
+    ) -> Result<IndexExpression<'a>, ParserError> {
+        let token = self.tok;
+        let indices = self.parse_index_list()?;
+        Ok(IndexExpression {
+            token,
+            span: subject.span().to(self.tok_span),
+            subject: Box::new(subject),
+            indices,
+        })
+    }
+
+    /// Parse the one or two comma separated expressions between square
+    /// brackets, used for both indexing and array sizes. Leaves the current
+    /// token at the `]`.
+    fn parse_index_list(&mut self) -> Result<Vec<Expr<'a>>, ParserError> {
+        // The current token should be the LSquareBracket
+        self.next_token()?;
+        let mut indices = vec![self.parse_expr(Precedence::Lowest)?];
+        self.next_token()?;
+        if self.tok == Token::Comma {
+            self.next_token()?;
+            indices.push(self.parse_expr(Precedence::Lowest)?);
+            self.next_token()?;
+        }
+        if self.tok != Token::RSquareBracket {
+            return Err(self.expected(TokenType::RSquareBracket));
+        }
+        Ok(indices)
+    }
+
     fn parse_prefix_expr(&mut self) -> Result<PrefixExpression<'a>, ParserError> {
+        let token = self.tok;
+        let start = self.tok_span;
+        let operator = match self.tok.try_into() {
+            Ok(p) => p,
+            Err(_) => return Err(self.unexpected_token()),
+        };
+        self.next_token()?;
+        let subject = self.parse_expr(Precedence::Prefix)?;
         Ok(PrefixExpression {
-            token:    self.tok,
-            operator: match self.tok.try_into() {
-                Ok(p) => p,
-                Err(_) => return Err(ParserError::UnexpectedToken(self.tok.into())),
-            },
-            subject:  {
-                self.next_token()?;
-                self.parse_expr(Precedence::Prefix)?
-            },
+            token,
+            span: start.to(subject.span()),
+            operator,
+            subject: Box::new(subject),
         })
     }
 
     fn parse_bool_expr(&mut self) -> Result<BooleanExpression<'a>, ParserError> {
         Ok(BooleanExpression {
             token: self.tok,
+            span:  self.tok_span,
             value: match self.tok {
                 Token::True => true,
                 Token::False => false,
-                _ => return Err(ParserError::UnexpectedToken(self.tok.into())),
+                _ => return Err(self.unexpected_token()),
             },
         })
     }
 
     fn parse_identifier(&mut self) -> Result<Identifier<'a>, ParserError> {
         if let Token::Identifier(_) = self.tok {
-            Ok(Identifier { token: self.tok })
+            Ok(self.spanned().into())
         } else {
-            Err(ParserError::UnexpectedToken(self.tok.into()))
+            Err(self.expected(TokenType::Identifier))
         }
     }
 
@@ -279,63 +551,93 @@ impl<'a> Parser<'a> {
         let value = match token {
             Token::NumberLiteral(n) => match n.parse() {
                 Ok(i) => i,
-                _ => return Err(ParserError::UnexpectedToken(self.tok.into())),
+                _ => return Err(ParserError::TooLargeInteger(self.tok_span)),
+            },
+            _ => return Err(self.unexpected_token()),
+        };
+        Ok(IntegerLiteralExpression {
+            token,
+            span: self.tok_span,
+            value,
+        })
+    }
+
+    fn parse_real_literal_expr(&mut self) -> Result<RealLiteralExpression<'a>, ParserError> {
+        let token = self.tok;
+        let value = match token {
+            Token::NumberLiteral(n) => match n.parse::<f64>() {
+                Ok(r) if r.is_finite() => r,
+                _ => return Err(ParserError::InvalidNumberLiteral(self.tok_span)),
             },
-            _ => return Err(ParserError::UnexpectedToken(self.tok.into())),
+            _ => return Err(self.unexpected_token()),
         };
-        Ok(IntegerLiteralExpression { token, value })
+        Ok(RealLiteralExpression {
+            token,
+            span: self.tok_span,
+            value,
+        })
+    }
+
+    fn parse_string_literal_expr(&mut self) -> Result<StringLiteralExpression<'a>, ParserError> {
+        let token = self.tok;
+        let value = match token {
+            Token::StringLiteral(s) => s,
+            _ => return Err(self.unexpected_token()),
+        };
+        Ok(StringLiteralExpression {
+            token,
+            span: self.tok_span,
+            value,
+        })
     }
 
-    fn parse_function(&mut self) -> Result<FunctionStatement<'a>, ParserError> {
+    /// Methods are parsed the same way as functions, except that they can be
+    /// called `new`
+    fn parse_function(&mut self, is_method: bool) -> Result<FunctionStatement<'a>, ParserError> {
         let token = self.tok;
+        let start = self.tok_span;
         let is_procedure = matches!(self.tok, Token::Procedure);
         self.next_token()?;
         let ident = match self.tok {
-            Token::Identifier(_) => self.tok.into(),
-            _ => return Err(ParserError::UnexpectedToken(self.tok.into())),
+            Token::Identifier(_) => self.spanned().into(),
+            Token::New if is_method => self.constructor_ident(),
+            _ => return Err(self.expected(TokenType::Identifier)),
         };
 
         self.next_token()?;
         if !matches!(self.tok, Token::LParenthasis) {
-            return Err(ParserError::UnexpectedToken(self.tok.into()));
+            return Err(self.expected(TokenType::LParenthasis));
         }
 
         let mut params = Vec::new();
         loop {
             self.next_token()?;
             match self.tok {
-                Token::Identifier(_) => params.push(self.tok.into()),
+                Token::Identifier(_) => params.push(self.parse_parameter()?),
                 Token::RParenthasis => break,
-                _ => return Err(ParserError::UnexpectedToken(self.tok.into())),
+                _ => return Err(self.expected(TokenType::Identifier)),
             };
 
             self.next_token()?;
             match self.tok {
                 Token::Comma => (),
                 Token::RParenthasis => break,
-                _ => return Err(ParserError::UnexpectedToken(self.tok.into())),
+                _ => return Err(self.expected(TokenType::RParenthasis)),
             };
         }
         self.next_token()?; // Skip past the RParenthasis
         self.skip_newlines()?;
         let body = self.parse_block_statement()?;
-        match self.tok {
-            Token::Endfunction => {
-                if is_procedure {
-                    return Err(ParserError::UnexpectedToken(self.tok.into()));
-                }
-            }
-            Token::Endprocedure => {
-                if !is_procedure {
-                    return Err(ParserError::UnexpectedToken(self.tok.into()));
-                }
-            }
-            _ => return Err(ParserError::UnexpectedToken(self.tok.into())),
+        match (self.tok, is_procedure) {
+            (Token::Endfunction, false) | (Token::Endprocedure, true) => (),
+            (_, false) => return Err(self.expected(TokenType::Endfunction)),
+            (_, true) => return Err(self.expected(TokenType::Endprocedure)),
         }
         self.skip_newlines()?;
 
         Ok(FunctionStatement {
             token,
+            span: start.to(self.tok_span),
             is_procedure,
             body,
             ident,
@@ -343,111 +645,466 @@ impl<'a> Parser<'a> {
         })
     }
 
+    fn parse_class(&mut self) -> Result<ClassStatement<'a>, ParserError> {
+        // class <ident> (inherits <ident>)
+        //    ((public|private) <ident>)
+        //    ((public|private) <function or procedure>)
+        // endclass
+        let token = self.tok;
+        let start = self.tok_span;
+        self.next_token()?;
+        let ident = self.parse_identifier()?;
+        let parent = if self.peek_tok == Token::Inherits {
+            self.next_token()?;
+            self.next_token()?;
+            Some(self.parse_identifier()?)
+        } else {
+            None
+        };
+        self.next_token()?;
+        self.skip_newlines()?;
+
+        let mut attributes = Vec::new();
+        let mut methods = Vec::new();
+        while self.tok != Token::Endclass {
+            let visibility = match self.tok {
+                Token::Private => Visibility::Private,
+                _ => Visibility::Public,
+            };
+            if matches!(self.tok, Token::Public | Token::Private) {
+                self.next_token()?;
+            }
+            match self.tok {
+                Token::Function | Token::Procedure => methods.push(ClassMethod {
+                    visibility,
+                    func: self.parse_function(true)?,
+                }),
+                Token::Identifier(_) => {
+                    attributes.push(ClassAttribute {
+                        visibility,
+                        ident: self.spanned().into(),
+                    });
+                    if self.peek_tok != Token::Newline {
+                        self.next_token()?;
+                        return Err(self.expected(TokenType::Newline));
+                    }
+                }
+                Token::Eof => return Err(self.expected(TokenType::Endclass)),
+                _ => return Err(self.expected(TokenType::Identifier)),
+            }
+            self.next_token()?;
+            self.skip_newlines()?;
+        }
+
+        Ok(ClassStatement {
+            token,
+            span: start.to(self.tok_span),
+            ident,
+            parent,
+            attributes,
+            methods,
+        })
+    }
+
+    /// `<ident>(:byVal|:byRef)`, leaves the current token at the end of it
+    fn parse_parameter(&mut self) -> Result<Parameter<'a>, ParserError> {
+        let ident = self.parse_identifier()?;
+        if self.peek_tok != Token::Colon {
+            return Ok(Parameter {
+                ident,
+                pass_by: None,
+            });
+        }
+        self.next_token()?;
+        self.next_token()?;
+        let pass_by = match self.tok {
+            Token::ByVal => PassBy::Value,
+            Token::ByRef => PassBy::Reference,
+            _ => return Err(self.expected(TokenType::ByRef)),
+        };
+        Ok(Parameter {
+            ident,
+            pass_by: Some(pass_by),
+        })
+    }
+
     fn parse_if_statement(&mut self) -> Result<IfStatement<'a>, ParserError> {
         // If <expr> then
         //    <block>
+        // (elseif <expr> then
+        //    <block>)*
         // (else
         //    <block>)
         // endif
         let token = self.tok;
+        let start = self.tok_span;
         self.next_token()?;
+        let (condition, consequence) = self.parse_condition_and_block()?;
+        let mut elseifs = Vec::new();
+        while let Token::Elseif = self.tok {
+            self.next_token()?;
+            elseifs.push(self.parse_condition_and_block()?);
+        }
+        let alternative = if let Token::Else = self.tok {
+            self.next_token()?;
+            Some(self.parse_block_statement()?)
+        } else {
+            None
+        };
+        if !matches!(self.tok, Token::Endif) {
+            return Err(self.expected(TokenType::Endif));
+        }
+
+        Ok(IfStatement {
+            token,
+            span: start.to(self.tok_span),
+            condition,
+            consequence,
+            elseifs,
+            alternative,
+        })
+    }
+
+    /// `<expr> then <block>`, shared by `if` and `elseif`
+    fn parse_condition_and_block(
+        &mut self,
+    ) -> Result<(Box<Expr<'a>>, BlockStatement<'a>), ParserError> {
         let condition = self.parse_expr(Precedence::Lowest)?;
 
         self.next_token()?;
         if !matches!(self.tok, Token::Then) {
-            return Err(ParserError::UnexpectedToken(self.tok.into()));
+            return Err(self.expected(TokenType::Then));
         }
         self.next_token()?;
 
-        let consequence = self.parse_block_statement()?;
+        Ok((Box::new(condition), self.parse_block_statement()?))
+    }
 
-        Ok(IfStatement {
+    fn parse_for_statement(&mut self) -> Result<ForStatement<'a>, ParserError> {
+        // for <ident> = <expr> to <expr> (step <expr>)
+        //    <block>
+        // next <ident>
+        let token = self.tok;
+        let start_span = self.tok_span;
+        self.next_token()?;
+        let counter: Identifier = match self.tok {
+            Token::Identifier(_) => self.spanned().into(),
+            _ => return Err(self.expected(TokenType::Identifier)),
+        };
+
+        self.next_token()?;
+        if !matches!(self.tok, Token::Equals) {
+            return Err(self.expected(TokenType::Equals));
+        }
+        self.next_token()?;
+        let start = self.parse_expr(Precedence::Lowest)?;
+
+        self.next_token()?;
+        if !matches!(self.tok, Token::To) {
+            return Err(self.expected(TokenType::To));
+        }
+        self.next_token()?;
+        let end = self.parse_expr(Precedence::Lowest)?;
+
+        let step = if matches!(self.peek_tok, Token::Step) {
+            self.next_token()?;
+            self.next_token()?;
+            Some(Box::new(self.parse_expr(Precedence::Lowest)?))
+        } else {
+            None
+        };
+        self.next_token()?;
+
+        let body = self.parse_block_statement()?;
+        if !matches!(self.tok, Token::Next) {
+            return Err(self.expected(TokenType::Next));
+        }
+        self.next_token()?;
+        match self.tok {
+            Token::Identifier(i) if i == counter.get_ident() => (),
+            Token::Identifier(_) => return Err(ParserError::MismatchedLoopCounter(self.tok_span)),
+            _ => return Err(self.expected(TokenType::Identifier)),
+        }
+
+        Ok(ForStatement {
             token,
-            condition,
-            consequence,
-            alternative: {
-                if let Token::Else = self.tok {
+            span: start_span.to(self.tok_span),
+            counter,
+            start: Box::new(start),
+            end: Box::new(end),
+            step,
+            body,
+        })
+    }
+
+    fn parse_while_statement(&mut self) -> Result<WhileStatement<'a>, ParserError> {
+        // while <expr>
+        //    <block>
+        // endwhile
+        let token = self.tok;
+        let start = self.tok_span;
+        self.next_token()?;
+        let condition = self.parse_expr(Precedence::Lowest)?;
+        self.next_token()?;
+
+        let body = self.parse_block_statement()?;
+        if !matches!(self.tok, Token::Endwhile) {
+            return Err(self.expected(TokenType::Endwhile));
+        }
+
+        Ok(WhileStatement {
+            token,
+            span: start.to(self.tok_span),
+            condition: Box::new(condition),
+            body,
+        })
+    }
+
+    fn parse_do_until_statement(&mut self) -> Result<DoUntilStatement<'a>, ParserError> {
+        // do
+        //    <block>
+        // until <expr>
+        let token = self.tok;
+        let start = self.tok_span;
+        self.next_token()?;
+
+        let body = self.parse_block_statement()?;
+        if !matches!(self.tok, Token::Until) {
+            return Err(self.expected(TokenType::Until));
+        }
+        self.next_token()?;
+        let condition = self.parse_expr(Precedence::Lowest)?;
+
+        Ok(DoUntilStatement {
+            token,
+            span: start.to(self.tok_span),
+            body,
+            condition: Box::new(condition),
+        })
+    }
+
+    fn parse_switch_statement(&mut self) -> Result<SwitchStatement<'a>, ParserError> {
+        // switch <expr>:
+        //    case <expr>:
+        //        <block>
+        //    (default:
+        //        <block>)
+        // endswitch
+        let token = self.tok;
+        let start = self.tok_span;
+        self.next_token()?;
+        let subject = self.parse_expr(Precedence::Lowest)?;
+        self.next_token()?;
+        self.expect_colon()?;
+        self.skip_newlines()?;
+
+        let mut cases = Vec::new();
+        let mut default = None;
+        loop {
+            match self.tok {
+                Token::Case => {
+                    let token = self.tok;
+                    let start = self.tok_span;
+                    self.next_token()?;
+                    let value = self.parse_expr(Precedence::Lowest)?;
+                    self.next_token()?;
+                    self.expect_colon()?;
+                    let body = self.parse_block_statement()?;
+                    cases.push(SwitchCase {
+                        token,
+                        span: start.to(body.span),
+                        value: Box::new(value),
+                        body,
+                    });
+                }
+                // The default case has to be the last one
+                Token::Default => {
                     self.next_token()?;
-                    let block = self.parse_block_statement()?;
-                    if !matches!(self.tok, Token::Endif) {
-                        return Err(ParserError::UnexpectedToken(self.tok.into()));
+                    self.expect_colon()?;
+                    default = Some(self.parse_block_statement()?);
+                    if !matches!(self.tok, Token::Endswitch) {
+                        return Err(self.expected(TokenType::Endswitch));
                     }
-                    Some(block)
-                } else {
-                    None
                 }
-            },
+                Token::Endswitch => break,
+                _ => return Err(self.unexpected_token()),
+            }
+        }
+
+        Ok(SwitchStatement {
+            token,
+            span: start.to(self.tok_span),
+            subject: Box::new(subject),
+            cases,
+            default,
         })
     }
 
+    /// Check that the current token is a colon, then skip past it
+    fn expect_colon(&mut self) -> Result<(), ParserError> {
+        if !matches!(self.tok, Token::Colon) {
+            return Err(self.expected(TokenType::Colon));
+        }
+        self.next_token()?;
+        Ok(())
+    }
+
     fn parse_block_statement(&mut self) -> Result<BlockStatement<'a>, ParserError> {
         let mut block = BlockStatement {
             token:      self.tok,
+            span:       Span::default(),
             statements: vec![],
         };
+        self.skip_newlines()?;
+        // An empty block still needs somewhere to point at
+        block.span = self.tok_span.empty();
         while !(self.tok.is_block_ender() || matches!(self.tok, Token::Eof)) {
-            if let Some(s) = self.parse_statement()? {
+            let start = self.tok_span;
+            let (stmt, recovered) = match self.parse_statement() {
+                Err(e) if self.recovering => (Some(self.recover(e, start)?), true),
+                stmt => (stmt?, false),
+            };
+            if let Some(s) = stmt {
+                block.span = match block.statements.first() {
+                    Some(first) => first.span().to(s.span()),
+                    None => s.span(),
+                };
                 block.statements.push(s);
             }
 
             self.skip_newlines()?;
-            self.next_token()?;
-            self.skip_newlines()?;
+            // Stopping at a block ender means that it's the end of this block, so it
+            // mustn't be skipped
+            if !recovered {
+                self.next_token()?;
+                self.skip_newlines()?;
+            }
         }
         Ok(block)
     }
 
     fn parse_return_statement(&mut self) -> Result<ReturnStatement<'a>, ParserError> {
         let token = self.tok;
-        match self.peek_tok {
-            Token::Newline | Token::Eof => Ok(ReturnStatement { token, value: None }),
-            _ => Ok(ReturnStatement {
-                token,
-                value: {
-                    let prec: Precedence = self.tok.into();
-                    match self.peek_tok {
-                        Token::Newline | Token::Eof => None,
-                        _ => {
-                            self.next_token()?;
-                            Some(self.parse_expr(prec)?)
-                        }
-                    }
-                },
-            }),
+        let start = self.tok_span;
+        let value = match self.peek_tok {
+            Token::Newline | Token::Eof => None,
+            _ => {
+                let prec: Precedence = self.tok.into();
+                self.next_token()?;
+                Some(Box::new(self.parse_expr(prec)?))
+            }
+        };
+        Ok(ReturnStatement {
+            token,
+            span: start.to(self.tok_span),
+            value,
+        })
+    }
+
+    fn parse_array_declaration(&mut self) -> Result<ArrayDeclarationStatement<'a>, ParserError> {
+        let token = self.tok;
+        let start = self.tok_span;
+        self.next_token()?;
+        let ident: Identifier = match self.tok {
+            Token::Identifier(_) => self.spanned().into(),
+            _ => return Err(self.expected(TokenType::Identifier)),
+        };
+        self.next_token()?;
+        if self.tok != Token::LSquareBracket {
+            return Err(self.expected(TokenType::LSquareBracket));
+        }
+        let sizes = self.parse_index_list()?;
+        Ok(ArrayDeclarationStatement {
+            token,
+            span: start.to(self.tok_span),
+            ident,
+            sizes,
+        })
+    }
+
+    /// Whether the current identifier starts an assignment to an element of an
+    /// array, like `board[x, y] = 1`, rather than an expression using one.
+    /// Looks ahead on a copy of the lexer for an `=` after the matching
+    /// `]`.
+    fn is_index_assignment(&self) -> bool {
+        if self.peek_tok != Token::LSquareBracket {
+            return false;
+        }
+        let mut lexer = self.lexer;
+        let mut depth = 1;
+        while depth > 0 {
+            match lexer.next_token() {
+                Ok(Token::LSquareBracket) => depth += 1,
+                Ok(Token::RSquareBracket) => depth -= 1,
+                Ok(Token::Newline | Token::Eof) | Err(_) => return false,
+                Ok(_) => (),
+            }
         }
+        matches!(lexer.next_token(), Ok(Token::Equals))
+    }
+
+    /// Whether the current identifier starts an assignment to an attribute of
+    /// an object, like `pet.name = "Rex"`
+    fn is_member_assignment(&self) -> bool {
+        if self.peek_tok != Token::Dot {
+            return false;
+        }
+        let mut lexer = self.lexer;
+        matches!(lexer.next_token(), Ok(Token::Identifier(_)))
+            && matches!(lexer.next_token(), Ok(Token::Equals))
     }
 
     fn parse_assign_statement(&mut self) -> Result<AssignStatement<'a>, ParserError> {
         let token = self.tok;
-        let ident;
+        let start = self.tok_span;
+        let ident: Identifier;
         let mut global = false;
         match self.tok {
             Token::Global => {
                 self.next_token()?;
                 global = true;
                 match self.tok {
-                    Token::Identifier(_) => ident = self.tok,
-                    _ => return Err(ParserError::UnexpectedToken(self.tok.into())),
+                    Token::Identifier(_) => ident = self.spanned().into(),
+                    _ => return Err(self.expected(TokenType::Identifier)),
                 };
             }
-            Token::Identifier(_) => ident = self.tok,
-            _ => return Err(ParserError::UnexpectedToken(self.tok.into())),
+            Token::Identifier(_) => ident = self.spanned().into(),
+            _ => return Err(self.unexpected_token()),
         }
         self.next_token()?;
 
+        let target = if !global && self.tok == Token::LSquareBracket {
+            let index = self.parse_index_expression(Expr::Identifier(ident))?;
+            self.next_token()?;
+            AssignTarget::Index(index)
+        } else if !global && self.tok == Token::Dot {
+            let token = self.tok;
+            self.next_token()?;
+            let member = self.parse_identifier()?;
+            self.next_token()?;
+            AssignTarget::Member(MemberAccessExpression {
+                token,
+                span: ident.span.to(member.span),
+                subject: Box::new(Expr::Identifier(ident)),
+                member,
+            })
+        } else {
+            AssignTarget::Identifier(ident)
+        };
+
         if !matches!(self.tok, Token::Equals) {
-            return Err(ParserError::UnexpectedToken(self.tok.into()));
+            return Err(self.expected(TokenType::Equals));
         }
 
+        let prec: Precedence = self.tok.into();
+        self.next_token()?;
+        let value = self.parse_expr(prec)?;
         Ok(AssignStatement {
             token,
+            span: start.to(value.span()),
             global,
-            ident: Identifier { token: ident },
-            value: {
-                let prec: Precedence = self.tok.into();
-                self.next_token()?;
-                self.parse_expr(prec)?
-            },
+            target,
+            value: Box::new(value),
         })
     }
 
@@ -459,20 +1116,67 @@ impl<'a> Parser<'a> {
     }
 
     pub fn next_token(&mut self) -> Result<(), LexerError> {
+        if !matches!(self.tok, Token::Newline) {
+            self.last_span = self.tok_span;
+        }
         self.tok = self.peek_tok;
-        self.peek_tok = self.lexer.next_token()?;
+        self.tok_span = self.peek_span;
+        let next = match self.lexer.next_spanned_token() {
+            Err(e) if self.recovering => {
+                self.errors.push(e.into());
+                match e {
+                    // Carry on as if the string was terminated at the end of the input, where
+                    // the lexer has stopped
+                    LexerError::UnterminatedStringLiteral(span) => SpannedToken {
+                        tok: Token::StringLiteral(""),
+                        span,
+                    },
+                }
+            }
+            next => next?,
+        };
+        self.peek_tok = next.tok;
+        self.peek_span = next.span;
         Ok(())
     }
 
+    fn spanned(&self) -> SpannedToken<'a> {
+        SpannedToken {
+            tok:  self.tok,
+            span: self.tok_span,
+        }
+    }
+
+    fn unexpected_token(&self) -> ParserError {
+        ParserError::UnexpectedToken(self.spanned().into())
+    }
+
+    fn expected(&self, tok_type: TokenType) -> ParserError {
+        ParserError::ExpectedToken(tok_type, self.spanned().into())
+    }
+
+    fn expected_expression(&self) -> ParserError {
+        ParserError::ExpectedExpression(self.spanned().into())
+    }
+
     /// Might error in the rare case that the lexer is unable to continue in the
     /// first 2 tokens, for example an integer that's too big or an invalid
     /// string literal
     pub fn new(input: Lexer<'a>) -> Result<Self, LexerError> {
+        Self::with_recovering(input, false)
+    }
+
+    fn with_recovering(input: Lexer<'a>, recovering: bool) -> Result<Self, LexerError> {
         let mut p = Self {
-            lexer:    input,
-            tok:      Token::default(),
+            lexer: input,
+            tok: Token::default(),
+            tok_span: Span::default(),
             peek_tok: Token::default(),
-            prog:     Program::default(),
+            peek_span: Span::default(),
+            prog: Program::default(),
+            last_span: Span::default(),
+            recovering,
+            errors: Vec::new(),
         };
         // Read 2 tokens, so tok and read_tok are both set properly
         p.next_token()?;
@@ -481,14 +1185,30 @@ impl<'a> Parser<'a> {
     }
 }
 
-pub fn parse_from_lexer(input: Lexer) -> Result<Program, ParserError> {
-    let mut parser = Parser::new(input).unwrap();
+pub fn parse_from_lexer(input: Lexer<'_>) -> Result<Program<'_>, ParserError> {
+    let mut parser = Parser::new(input)?;
     parser.parse()?;
     Ok(std::mem::take(&mut parser.prog))
 }
 
-pub fn parse_from_string(input: &str) -> Result<Program, ParserError> {
-    let mut parser = Parser::new(Lexer::new(input)).unwrap();
+pub fn parse_from_string(input: &str) -> Result<Program<'_>, ParserError> {
+    let mut parser = Parser::new(Lexer::new(input))?;
     parser.parse()?;
     Ok(std::mem::take(&mut parser.prog))
 }
+
+/// Parse as much of the input as possible, collecting every error rather than
+/// stopping at the first one. Statements which failed to parse are replaced by
+/// an `ErrorStatement` in the returned program.
+pub fn parse_with_recovery(input: &str) -> (Program<'_>, Vec<ParserError>) {
+    parse_from_lexer_with_recovery(Lexer::new(input))
+}
+
+/// Like `parse_with_recovery`, for a lexer which has been set up already
+pub fn parse_from_lexer_with_recovery(input: Lexer<'_>) -> (Program<'_>, Vec<ParserError>) {
+    // Lexer errors are collected rather than returned when recovering, and
+    // nothing else can fail
+    let mut parser = Parser::with_recovering(input, true).unwrap();
+    parser.parse_recovering().unwrap();
+    (std::mem::take(&mut parser.prog), parser.errors)
+}
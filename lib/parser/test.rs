@@ -1,12 +1,22 @@
 use super::parse_from_string;
+use super::parse_with_recovery;
+use super::ParserError;
+use crate::lexer::Span;
+use crate::lexer::TokenType;
+use crate::syntax::AssignTarget;
+use crate::syntax::AstNode;
+use crate::syntax::Expr;
 use crate::syntax::PrettyPrint;
-use crate::syntax::StatementType;
+use crate::syntax::Stmt;
 
 #[test]
 fn test_parse_var_assign_statement() {
     let input = "a=1
 global bb=22
 ccc=333";
+    let expected = "a = 1
+global bb = 22
+ccc = 333";
     let prog = parse_from_string(input).unwrap();
     assert_eq!(prog.statements.len(), input.lines().count());
     assert_eq!(
@@ -15,7 +25,7 @@ ccc=333";
             .map(|stmt| stmt.pretty_print())
             .collect::<Vec<String>>()
             .join("\n"),
-        input
+        expected
     );
 }
 
@@ -68,6 +78,54 @@ fn test_parse_number_literal_expr() {
     );
 }
 
+#[test]
+fn test_parse_real_literal_expr() {
+    let input = "3.14
+0.5
+1e3
+2.5e-3";
+    let prog = parse_from_string(input).unwrap();
+    assert_eq!(
+        prog.statements
+            .iter()
+            .map(|stmt| stmt.pretty_print())
+            .collect::<Vec<String>>(),
+        ["3.14", "0.5", "1e3", "2.5e-3"]
+    );
+    // Once the value changes it is printed from the value instead
+    let mut prog = parse_from_string("1e3").unwrap();
+    if let Stmt::Expression(e) = &mut prog.statements[0] {
+        if let Expr::RealLiteral(r) = &mut *e.value {
+            r.value = 2.0;
+        }
+    }
+    assert_eq!(prog.statements[0].pretty_print(), "2.0");
+    assert!(matches!(
+        parse_from_string("1e999"),
+        Err(ParserError::InvalidNumberLiteral(_))
+    ));
+}
+
+#[test]
+fn test_parse_string_literal_expr() {
+    let input = "\"hello\"
+name=\"Bob\"
+\"\"";
+    let expected = "\"hello\"
+name = \"Bob\"
+\"\"";
+    let prog = parse_from_string(input).unwrap();
+    assert_eq!(prog.statements.len(), input.lines().count());
+    assert_eq!(
+        prog.statements
+            .iter()
+            .map(|stmt| stmt.pretty_print())
+            .collect::<Vec<String>>()
+            .join("\n"),
+        expected
+    );
+}
+
 #[test]
 fn test_parse_bool_expr() {
     let input = "true
@@ -116,6 +174,19 @@ fn test_parse_infix_expressions() {
 69 MOD 420
 69 DIV 420
 69!=420
+true OR false";
+    let expected = "69 + 420
+69 - 420
+69 * 420
+69 / 420
+69 < 420
+69 <= 420
+69 > 420
+69 >= 420
+69 == 420
+69 MOD 420
+69 DIV 420
+69 != 420
 true OR false";
     let prog = parse_from_string(input).unwrap();
     assert_eq!(prog.statements.len(), input.lines().count());
@@ -126,7 +197,7 @@ true OR false";
             .map(|stmt| stmt.pretty_print())
             .collect::<Vec<String>>()
             .join("\n"),
-        input
+        expected
     );
 }
 
@@ -134,6 +205,9 @@ true OR false";
 fn test_infix_expression_precedence() {
     let input = [
         ["5+5", "(5+5)"],
+        ["a == 1 OR b < 2", "((a==1) OR (b<2))"],
+        ["a OR b != c", "(a OR (b!=c))"],
+        ["a OR b AND c", "(a OR (b AND c))"],
         ["-a+b", "((-a)+b)"],
         ["-a+b * NOT c", "((-a)+(b*(NOT c)))"],
         ["5*5+5", "((5*5)+5)"],
@@ -143,17 +217,20 @@ fn test_infix_expression_precedence() {
         ["5 MOD 5+5", "((5 MOD 5)+5)"],
         ["5 MOD 5*5", "((5 MOD 5)*5)"],
         ["5 MOD 5*5", "((5 MOD 5)*5)"],
+        ["2^3^2", "(2^(3^2))"],
+        ["2*3^2", "(2*(3^2))"],
+        ["2^3*2", "((2^3)*2)"],
+        ["-2^2", "(-(2^2))"],
+        ["2^-1", "(2^(-1))"],
+        ["a[1]^2", "(a[1]^2)"],
     ];
     let input_lines = input.map(|l| l[0]).join("\n");
     let prog = parse_from_string(&input_lines).unwrap();
     assert_eq!(prog.statements.len(), input.len());
 
     for (i, line) in input.iter().enumerate() {
-        assert!(matches!(
-            prog.statements[i].get_type(),
-            StatementType::Expression(_)
-        ));
-        if let StatementType::Expression(x) = prog.statements[i].get_type() {
+        assert!(matches!(&prog.statements[i], Stmt::Expression(_)));
+        if let Stmt::Expression(x) = &prog.statements[i] {
             assert_eq!(x.value.pretty_print_with_brackets(), line[1]);
         }
     }
@@ -172,11 +249,8 @@ fn test_parse_expr_with_paren() {
     assert_eq!(prog.statements.len(), input.len());
 
     for (i, line) in input.iter().enumerate() {
-        assert!(matches!(
-            prog.statements[i].get_type(),
-            StatementType::Expression(_)
-        ));
-        if let StatementType::Expression(x) = prog.statements[i].get_type() {
+        assert!(matches!(&prog.statements[i], Stmt::Expression(_)));
+        if let Stmt::Expression(x) = &prog.statements[i] {
             assert_eq!(x.value.pretty_print_with_brackets(), line[1]);
         }
     }
@@ -200,25 +274,63 @@ fn test_parse_if_statement() {
     let prog = parse_from_string(&input_lines).unwrap();
     assert_eq!(prog.statements.len(), input.len());
 
-    assert!(matches!(
-        prog.statements[0].get_type(),
-        StatementType::If(_)
-    ));
-    if let StatementType::If(i) = prog.statements[0].get_type() {
-        assert_eq!(i.condition.pretty_print(), "true!=false");
-        assert_eq!(i.consequence.pretty_print(), "x=5\ny=x-5");
+    assert!(matches!(&prog.statements[0], Stmt::If(_)));
+    if let Stmt::If(i) = &prog.statements[0] {
+        assert_eq!(i.condition.pretty_print(), "true != false");
+        assert_eq!(i.consequence.pretty_print(), "x = 5\ny = x - 5");
         assert!(i.alternative.is_some());
-        assert_eq!(i.alternative.as_ref().unwrap().pretty_print(), "y=5\nx=y-5");
+        assert_eq!(
+            i.alternative.as_ref().unwrap().pretty_print(),
+            "y = 5\nx = y - 5"
+        );
+    }
+
+    assert!(matches!(&prog.statements[1], Stmt::If(_)));
+    if let Stmt::If(i) = &prog.statements[1] {
+        assert_eq!(i.condition.pretty_print(), "x + y > 5");
+        assert_eq!(i.consequence.pretty_print(), "x = y + 5");
+    }
+}
+
+#[test]
+fn test_parse_elseif_chain() {
+    let input = "if x == 1 then
+    y = 1
+elseif x == 2 then
+    y = 2
+elseif x == 3 then
+else
+    y = 4
+endif";
+    let prog = parse_from_string(input).unwrap();
+    assert_eq!(prog.statements.len(), 1);
+
+    if let Stmt::If(i) = &prog.statements[0] {
+        assert_eq!(i.elseifs.len(), 2);
+        assert_eq!(i.elseifs[0].0.pretty_print(), "x == 2");
+        assert_eq!(i.elseifs[0].1.pretty_print(), "y = 2");
+        assert_eq!(i.elseifs[1].0.pretty_print(), "x == 3");
+        assert!(i.elseifs[1].1.statements.is_empty());
+        assert_eq!(
+            i.pretty_print(),
+            "if x == 1 then
+    y = 1
+elseif x == 2 then
+    y = 2
+elseif x == 3 then
+
+else
+    y = 4
+endif"
+        );
+    } else {
+        panic!("not an if statement");
     }
 
     assert!(matches!(
-        prog.statements[1].get_type(),
-        StatementType::If(_)
+        parse_from_string("if x then\nelseif y\nendif"),
+        Err(ParserError::ExpectedToken(TokenType::Then, _))
     ));
-    if let StatementType::If(i) = prog.statements[1].get_type() {
-        assert_eq!(i.condition.pretty_print(), "x+y>5");
-        assert_eq!(i.consequence.pretty_print(), "x=y+5");
-    }
 }
 
 #[test]
@@ -239,32 +351,26 @@ fn test_parse_function() {
     let prog = parse_from_string(&input_lines).unwrap();
     assert_eq!(prog.statements.len(), input.len());
 
-    assert!(matches!(
-        prog.statements[0].get_type(),
-        StatementType::Function(_)
-    ));
-    if let StatementType::Function(i) = prog.statements[0].get_type() {
+    assert!(matches!(&prog.statements[0], Stmt::Function(_)));
+    if let Stmt::Function(i) = &prog.statements[0] {
         assert_eq!(
             i.pretty_print(),
             "function my_func(arg1, arg2)
-x=1
-y=2
-x=x+1
-y=y+1
+    x = 1
+    y = 2
+    x = x + 1
+    y = y + 1
 endfunction"
         )
     }
 
-    assert!(matches!(
-        prog.statements[1].get_type(),
-        StatementType::Function(_)
-    ));
-    if let StatementType::Function(i) = prog.statements[1].get_type() {
+    assert!(matches!(&prog.statements[1], Stmt::Function(_)));
+    if let Stmt::Function(i) = &prog.statements[1] {
         assert_eq!(
             i.pretty_print(),
             "procedure my_proc()
-y=1+2+3+4+5
-y=-1/12
+    y = 1 + 2 + 3 + 4 + 5
+    y = -1 / 12
 endprocedure"
         )
     }
@@ -276,9 +382,520 @@ fn test_parse_function_call() {
 y=second_function(1, x+3, y)";
     let prog = parse_from_string(input).unwrap();
 
-    assert_eq!(prog.statements[0].pretty_print(), "x=first_function()");
+    assert_eq!(prog.statements[0].pretty_print(), "x = first_function()");
     assert_eq!(
         prog.statements[1].pretty_print(),
-        "y=second_function(1, x+3, y)"
+        "y = second_function(1, x + 3, y)"
+    );
+}
+
+#[test]
+fn test_parse_call_of_non_identifier() {
+    for input in ["a[0](7)", "f(1)(2)", "(a + b)(1)"] {
+        assert!(
+            matches!(
+                parse_from_string(input),
+                Err(ParserError::UnexpectedToken(_))
+            ),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_parse_for_statement() {
+    let input = [
+        "for i = 0 to 9
+            x = x + i
+        next i",
+        "for j = 10 to 0 step -2
+
+        next j",
+    ];
+    let input_lines = input.join("\n");
+    let prog = parse_from_string(&input_lines).unwrap();
+    assert_eq!(prog.statements.len(), input.len());
+
+    assert!(matches!(&prog.statements[0], Stmt::For(_)));
+    if let Stmt::For(f) = &prog.statements[0] {
+        assert_eq!(f.counter.get_ident(), "i");
+        assert!(f.step.is_none());
+        assert_eq!(f.pretty_print(), "for i = 0 to 9\n    x = x + i\nnext i");
+    }
+
+    assert!(matches!(&prog.statements[1], Stmt::For(_)));
+    if let Stmt::For(f) = &prog.statements[1] {
+        assert_eq!(f.step.as_ref().unwrap().pretty_print(), "-2");
+        assert!(f.body.statements.is_empty());
+    }
+}
+
+#[test]
+fn test_parse_for_statement_mismatched_counter() {
+    let input = "for i = 0 to 9
+    x = i
+next j";
+    assert!(matches!(
+        parse_from_string(input),
+        Err(ParserError::MismatchedLoopCounter(Span {
+            start: 30,
+            end:   31,
+            line:  3,
+            col:   6,
+        }))
+    ));
+}
+
+#[test]
+fn test_parse_while_statement() {
+    let input = "while x<10
+x=x+1
+y=y*2
+endwhile";
+    let prog = parse_from_string(input).unwrap();
+    assert_eq!(prog.statements.len(), 1);
+
+    assert!(matches!(&prog.statements[0], Stmt::While(_)));
+    if let Stmt::While(w) = &prog.statements[0] {
+        assert_eq!(w.condition.pretty_print(), "x < 10");
+        assert_eq!(w.body.statements.len(), 2);
+    }
+    assert_eq!(
+        prog.statements[0].pretty_print(),
+        "while x < 10
+    x = x + 1
+    y = y * 2
+endwhile"
+    );
+}
+
+#[test]
+fn test_parse_do_until_statement() {
+    let input = "do
+x=x+1
+until x>=10 OR done";
+    let prog = parse_from_string(input).unwrap();
+    assert_eq!(prog.statements.len(), 1);
+
+    assert!(matches!(&prog.statements[0], Stmt::DoUntil(_)));
+    if let Stmt::DoUntil(d) = &prog.statements[0] {
+        assert_eq!(d.condition.pretty_print(), "x >= 10 OR done");
+        assert_eq!(d.body.pretty_print(), "x = x + 1");
+    }
+    assert_eq!(
+        prog.statements[0].pretty_print(),
+        "do
+    x = x + 1
+until x >= 10 OR done"
+    );
+}
+
+#[test]
+fn test_parse_switch_statement() {
+    let input = [
+        "switch day:
+            case 1:
+                x = 1
+                y = 2
+            case 2:
+            case a + b:
+                x = 3
+            default:
+                x = 0
+        endswitch",
+        "switch NOT done:
+            case true:
+                x = 1
+        endswitch",
+    ];
+    let input_lines = input.join("\n");
+    let prog = parse_from_string(&input_lines).unwrap();
+    assert_eq!(prog.statements.len(), input.len());
+
+    assert!(matches!(&prog.statements[0], Stmt::Switch(_)));
+    if let Stmt::Switch(s) = &prog.statements[0] {
+        assert_eq!(s.subject.pretty_print(), "day");
+        assert_eq!(s.cases.len(), 3);
+        assert_eq!(s.cases[0].body.pretty_print(), "x = 1\ny = 2");
+        assert!(s.cases[1].body.statements.is_empty());
+        assert_eq!(s.cases[2].value.pretty_print(), "a + b");
+        assert_eq!(s.default.as_ref().unwrap().pretty_print(), "x = 0");
+    }
+
+    assert!(matches!(&prog.statements[1], Stmt::Switch(_)));
+    if let Stmt::Switch(s) = &prog.statements[1] {
+        assert!(s.default.is_none());
+        assert_eq!(
+            s.pretty_print(),
+            "switch NOT done:
+    case true:
+        x = 1
+endswitch"
+        );
+    }
+}
+
+#[test]
+fn test_parse_switch_statement_default_not_last() {
+    let input = "switch x:
+    default:
+        y = 1
+    case 1:
+        y = 2
+endswitch";
+    assert!(matches!(
+        parse_from_string(input),
+        Err(ParserError::ExpectedToken(TokenType::Endswitch, _))
+    ));
+}
+
+#[test]
+fn test_parse_array_declaration() {
+    let input = ["array names[5]", "array board[8, size * 2]"];
+    let input_lines = input.join("\n");
+    let prog = parse_from_string(&input_lines).unwrap();
+    assert_eq!(prog.statements.len(), input.len());
+
+    for (i, expected) in ["array names[5]", "array board[8, size * 2]"]
+        .iter()
+        .enumerate()
+    {
+        assert!(matches!(&prog.statements[i], Stmt::ArrayDeclaration(_)));
+        assert_eq!(prog.statements[i].pretty_print(), *expected);
+    }
+    if let Stmt::ArrayDeclaration(a) = &prog.statements[1] {
+        assert_eq!(a.ident.get_ident(), "board");
+        assert_eq!(a.sizes.len(), 2);
+    }
+
+    assert!(matches!(
+        parse_from_string("array names"),
+        Err(ParserError::ExpectedToken(TokenType::LSquareBracket, _))
+    ));
+    assert!(matches!(
+        parse_from_string("array board[1, 2, 3]"),
+        Err(ParserError::ExpectedToken(TokenType::RSquareBracket, _))
+    ));
+}
+
+#[test]
+fn test_parse_index_expressions() {
+    let input = [
+        ["names[3]", "names[3]"],
+        ["board[x + 1, y]", "board[(x+1), y]"],
+        ["a[1] + b[2] * 3", "(a[1]+(b[2]*3))"],
+        ["-a[i]", "(-a[i])"],
+        ["f(x)[0]", "f(x)[0]"],
+        ["a[b[0]]", "a[b[0]]"],
+    ];
+    for [code, expected] in input {
+        let prog = parse_from_string(code).unwrap();
+        assert_eq!(prog.statements.len(), 1, "{}", code);
+        if let Stmt::Expression(e) = &prog.statements[0] {
+            assert_eq!(e.value.pretty_print_with_brackets(), expected, "{}", code);
+        } else {
+            panic!("{} is not an expression statement", code);
+        }
+    }
+}
+
+#[test]
+fn test_parse_index_assignment() {
+    let input = "names[0] = 1
+board[a[1], 2] = 3
+names[0] == 1";
+    let prog = parse_from_string(input).unwrap();
+    assert_eq!(prog.statements.len(), 3);
+
+    if let Stmt::Assign(a) = &prog.statements[0] {
+        assert!(matches!(a.target, AssignTarget::Index(_)));
+        assert_eq!(a.pretty_print(), "names[0] = 1");
+    } else {
+        panic!("expected an assignment");
+    }
+    if let Stmt::Assign(a) = &prog.statements[1] {
+        assert_eq!(a.pretty_print(), "board[a[1], 2] = 3");
+    } else {
+        panic!("expected an assignment");
+    }
+    assert!(matches!(&prog.statements[2], Stmt::Expression(_)));
+}
+
+#[test]
+fn test_parse_node_spans() {
+    let input = "x = 1 + foo(2)
+if x then
+    return -x
+endif";
+    let prog = parse_from_string(input).unwrap();
+    assert_eq!(prog.statements.len(), 2);
+    let span_text = |s: Span| &input[s.start..s.end];
+
+    assert_eq!(span_text(prog.statements[0].span()), "x = 1 + foo(2)");
+    assert!(matches!(&prog.statements[0], Stmt::Assign(_)));
+    if let Stmt::Assign(a) = &prog.statements[0] {
+        assert_eq!(span_text(a.target.span()), "x");
+        assert_eq!(span_text(a.value.span()), "1 + foo(2)");
+    }
+
+    assert_eq!(span_text(prog.statements[1].span()), &input[15..]);
+    assert_eq!(prog.statements[1].span().line, 2);
+    assert_eq!(prog.statements[1].span().col, 1);
+    assert!(matches!(&prog.statements[1], Stmt::If(_)));
+    if let Stmt::If(i) = &prog.statements[1] {
+        assert_eq!(span_text(i.consequence.span), "return -x");
+        assert_eq!(i.consequence.span.line, 3);
+        assert_eq!(i.consequence.span.col, 5);
+    }
+}
+
+#[test]
+fn test_parse_error_spans() {
+    let input = "x = 1
+y = (2 +
+z = 3";
+    let err = parse_from_string(input).unwrap_err();
+    assert!(matches!(err, ParserError::ExpectedExpression(_)));
+    assert_eq!(err.span().line, 2);
+    assert_eq!(err.span().col, 9);
+
+    let input = "x = \"never closed";
+    let err = parse_from_string(input).unwrap_err();
+    assert!(matches!(err, ParserError::UnterminatedStringLiteral(_)));
+    assert_eq!(err.span().start, 4);
+    assert_eq!(err.span().end, input.len());
+}
+
+#[test]
+fn test_parse_error_messages() {
+    let input = [
+        ["if x then\ny = 1", "expected `endif`, found end of file"],
+        ["if x\ny = 1\nendif", "expected `then`, found newline"],
+        [
+            "for 1 = 2 to 3\nnext i",
+            "expected identifier, found number",
+        ],
+        [
+            "function f()\nendprocedure",
+            "expected `endfunction`, found `endprocedure`",
+        ],
+        ["x = )", "expected an expression, found `)`"],
+        [
+            "for i = 0 to 1\nnext j",
+            "the identifier after `next` doesn't match the loop counter",
+        ],
+    ];
+    for [code, message] in input {
+        assert_eq!(parse_from_string(code).unwrap_err().to_string(), message);
+    }
+}
+
+#[test]
+fn test_parse_with_recovery() {
+    let input = "x = 1 +
+y = 2
+if x then
+    z = (3
+    w = 4
+endif
+while true
+    endfor
+endwhile
+return )
+v = 5";
+    let (prog, errors) = parse_with_recovery(input);
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<String>>(),
+        [
+            "expected an expression, found newline",
+            "expected `)`, found newline",
+            "expected `endwhile`, found `endfor`",
+            // Recovery stops at the stray `endfor`, so the real `endwhile` is left over
+            "expected an expression, found `endwhile`",
+            "expected an expression, found `)`",
+        ]
+    );
+    assert_eq!(
+        errors.iter().map(|e| e.span().line).collect::<Vec<usize>>(),
+        [1, 4, 8, 9, 10]
+    );
+
+    assert_eq!(
+        prog.statements
+            .iter()
+            .map(|stmt| stmt.pretty_print())
+            .collect::<Vec<String>>(),
+        [
+            "<ERROR_STATEMENT>",
+            "y = 2",
+            "if x then\n    <ERROR_STATEMENT>\n    w = 4\nendif",
+            "<ERROR_STATEMENT>",
+            "<ERROR_STATEMENT>",
+            "<ERROR_STATEMENT>",
+            "v = 5",
+        ]
     );
 }
+
+#[test]
+fn test_parse_with_recovery_no_errors() {
+    let input = "x = 1
+for i = 1 to 3
+    x = x * i
+next i";
+    let (prog, errors) = parse_with_recovery(input);
+    assert!(errors.is_empty());
+    assert_eq!(prog.statements.len(), 2);
+}
+
+#[test]
+fn test_parse_with_recovery_lexer_error() {
+    let input = "x = )
+y = \"unterminated";
+    let (prog, errors) = parse_with_recovery(input);
+    assert!(matches!(errors[0], ParserError::ExpectedExpression(_)));
+    assert!(matches!(
+        errors[1],
+        ParserError::UnterminatedStringLiteral(_)
+    ));
+    assert_eq!(errors.len(), 2);
+    assert_eq!(prog.statements.len(), 2);
+}
+
+#[test]
+fn test_parse_class() {
+    let input = "class Dog inherits Pet
+    private breed
+    name
+    public procedure new(givenName, givenBreed)
+        super.new(givenName)
+        breed = givenBreed
+    endprocedure
+    private function describe()
+        return name + breed
+    endfunction
+endclass";
+    let prog = parse_from_string(input).unwrap();
+    assert_eq!(prog.statements.len(), 1);
+    assert!(matches!(&prog.statements[0], Stmt::Class(_)));
+    assert_eq!(
+        prog.statements[0].pretty_print(),
+        "class Dog inherits Pet
+    private breed
+    public name
+    public procedure new(givenName, givenBreed)
+        super.new(givenName)
+        breed = givenBreed
+    endprocedure
+    private function describe()
+        return name + breed
+    endfunction
+endclass"
+    );
+}
+
+#[test]
+fn test_parse_class_errors() {
+    assert!(matches!(
+        parse_from_string("class Pet\nprivate name age\nendclass"),
+        Err(ParserError::ExpectedToken(TokenType::Newline, _))
+    ));
+    assert!(matches!(
+        parse_from_string("class Pet\nprivate name\n"),
+        Err(ParserError::ExpectedToken(TokenType::Endclass, _))
+    ));
+    assert!(matches!(
+        parse_from_string("procedure new()\nendprocedure"),
+        Err(ParserError::ExpectedToken(TokenType::Identifier, _))
+    ));
+}
+
+#[test]
+fn test_parse_member_expressions() {
+    let input = [
+        ["pet.name", "pet.name"],
+        ["new Dog(\"Rex\", 1 + 2)", "new Dog(\"Rex\", 1 + 2)"],
+        ["pet.speak(1, x * 2)", "pet.speak(1, (x*2))"],
+        ["-pet.age + 1", "((-pet.age)+1)"],
+        ["pets[0].owner.name", "pets[0].owner.name"],
+        ["new Dog().speak()", "new Dog().speak()"],
+        ["super.new()", "super.new()"],
+        ["name.length + 1", "(name.length+1)"],
+        ["s.substring(0, 3).upper", "s.substring(0, 3).upper"],
+        ["a.length * b.length", "(a.length*b.length)"],
+        ["\"abc\".left(x - 1)", "\"abc\".left((x-1))"],
+    ];
+    let input_lines = input.map(|l| l[0]).join("\n");
+    let prog = parse_from_string(&input_lines).unwrap();
+    assert_eq!(prog.statements.len(), input.len());
+
+    for (i, line) in input.iter().enumerate() {
+        if let Stmt::Expression(x) = &prog.statements[i] {
+            assert_eq!(x.value.pretty_print_with_brackets(), line[1]);
+        } else {
+            panic!("{} is not an expression statement", line[0]);
+        }
+    }
+}
+
+#[test]
+fn test_parse_member_assignment() {
+    let prog = parse_from_string("pet.name = \"Rex\"").unwrap();
+    assert!(matches!(&prog.statements[0], Stmt::Assign(_)));
+    if let Stmt::Assign(a) = &prog.statements[0] {
+        assert!(matches!(a.target, AssignTarget::Member(_)));
+        assert_eq!(a.pretty_print(), "pet.name = \"Rex\"");
+    }
+}
+
+#[test]
+fn test_parse_parameters() {
+    let prog = parse_from_string(
+        "procedure swap(a:byRef, b:byVal, c)
+    a = b
+endprocedure",
+    )
+    .unwrap();
+    assert_eq!(
+        prog.statements[0].pretty_print(),
+        "procedure swap(a:byRef, b:byVal, c)
+    a = b
+endprocedure"
+    );
+
+    assert!(matches!(
+        parse_from_string("procedure swap(a:foo)\nendprocedure"),
+        Err(ParserError::ExpectedToken(TokenType::ByRef, _))
+    ));
+}
+
+#[test]
+fn test_compare_parsed_trees() {
+    let input = "x = 1 + 2 * y
+if x > 3 then
+    print(x)
+endif";
+    let prog = parse_from_string(input).unwrap();
+    assert_eq!(
+        prog.statements,
+        parse_from_string(input).unwrap().statements
+    );
+
+    let mut changed = prog.statements.clone();
+    assert_eq!(changed, prog.statements);
+    if let Stmt::Assign(a) = &mut changed[0] {
+        a.global = true;
+    }
+    assert_ne!(changed, prog.statements);
+
+    // Spans aren't compared, so spacing doesn't matter but the structure does
+    let moved = parse_from_string("x=1+2*  y").unwrap();
+    assert_ne!(moved.statements[0].span(), prog.statements[0].span());
+    assert_eq!(moved.statements[0], prog.statements[0]);
+    let bracketed = parse_from_string("x = (1 + 2) * y").unwrap();
+    assert_ne!(bracketed.statements[0], prog.statements[0]);
+}
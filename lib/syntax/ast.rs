@@ -1,35 +1,239 @@
 use std::fmt::Debug;
 use std::fmt::Display;
 
+use crate::lexer::Span;
+use crate::lexer::SpannedToken;
 use crate::lexer::Token;
+use crate::parser::Precedence;
 
-/// Meta-trait for all of the stuff needed in AST statements/expressions
-pub trait AstNode: PrettyPrint + Debug {}
+/// Meta-trait for all of the stuff needed in AST nodes
+pub trait AstNode: PrettyPrint + Debug {
+    /// Where the node is in the source code, from its first token to its last
+    fn span(&self) -> Span;
+}
 
 /// Format the code in human readable form
 pub trait PrettyPrint {
     fn pretty_print(&self) -> String;
 }
 
-pub trait Statement: AstNode {
-    fn get_type(&self) -> StatementType;
+/// Indent every line of a block's body by four spaces
+fn indent(body: String) -> String {
+    body.lines()
+        .map(|l| {
+            if l.is_empty() {
+                String::new()
+            } else {
+                "    ".to_owned() + l
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
 }
 
-pub enum StatementType<'a> {
-    Assign(&'a AssignStatement<'a>),
-    Return(&'a ReturnStatement<'a>),
-    Expression(&'a ExpressionStatement<'a>),
-    If(&'a IfStatement<'a>),
-    Block(&'a BlockStatement<'a>),
-    Function(&'a FunctionStatement<'a>),
+/// A statement, which owns the node for whichever kind of statement it is
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", bound(deserialize = "'de: 'a")))]
+pub enum Stmt<'a> {
+    Assign(AssignStatement<'a>),
+    Return(ReturnStatement<'a>),
+    Expression(ExpressionStatement<'a>),
+    If(IfStatement<'a>),
+    Block(BlockStatement<'a>),
+    Function(FunctionStatement<'a>),
+    For(ForStatement<'a>),
+    While(WhileStatement<'a>),
+    DoUntil(DoUntilStatement<'a>),
+    Switch(SwitchStatement<'a>),
+    Error(ErrorStatement),
+    ArrayDeclaration(ArrayDeclarationStatement<'a>),
+    Class(ClassStatement<'a>),
     Empty,
 }
+impl PrettyPrint for Stmt<'_> {
+    fn pretty_print(&self) -> String {
+        match self {
+            Self::Assign(s) => s.pretty_print(),
+            Self::Return(s) => s.pretty_print(),
+            Self::Expression(s) => s.pretty_print(),
+            Self::If(s) => s.pretty_print(),
+            Self::Block(s) => s.pretty_print(),
+            Self::Function(s) => s.pretty_print(),
+            Self::For(s) => s.pretty_print(),
+            Self::While(s) => s.pretty_print(),
+            Self::DoUntil(s) => s.pretty_print(),
+            Self::Switch(s) => s.pretty_print(),
+            Self::Error(s) => s.pretty_print(),
+            Self::ArrayDeclaration(s) => s.pretty_print(),
+            Self::Class(s) => s.pretty_print(),
+            Self::Empty => String::new(),
+        }
+    }
+}
+impl AstNode for Stmt<'_> {
+    fn span(&self) -> Span {
+        match self {
+            Self::Assign(s) => s.span,
+            Self::Return(s) => s.span,
+            Self::Expression(s) => s.span,
+            Self::If(s) => s.span,
+            Self::Block(s) => s.span,
+            Self::Function(s) => s.span,
+            Self::For(s) => s.span,
+            Self::While(s) => s.span,
+            Self::DoUntil(s) => s.span,
+            Self::Switch(s) => s.span,
+            Self::Error(s) => s.span,
+            Self::ArrayDeclaration(s) => s.span,
+            Self::Class(s) => s.span,
+            Self::Empty => Span::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct ForStatement<'a> {
+    pub token:   Token<'a>,
+    pub span:    Span,
+    pub counter: Identifier<'a>,
+    pub start:   Box<Expr<'a>>,
+    pub end:     Box<Expr<'a>>,
+    pub step:    Option<Box<Expr<'a>>>,
+    pub body:    BlockStatement<'a>,
+}
+impl PrettyPrint for ForStatement<'_> {
+    fn pretty_print(&self) -> String {
+        "for ".to_owned()
+            + self.counter.get_ident()
+            + " = "
+            + &self.start.pretty_print()
+            + " to "
+            + &self.end.pretty_print()
+            + &match &self.step {
+                Some(s) => " step ".to_owned() + &s.pretty_print(),
+                None => String::new(),
+            }
+            + "\n"
+            + &indent(self.body.pretty_print())
+            + "\nnext "
+            + self.counter.get_ident()
+    }
+}
+impl AstNode for ForStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct WhileStatement<'a> {
+    pub token:     Token<'a>,
+    pub span:      Span,
+    pub condition: Box<Expr<'a>>,
+    pub body:      BlockStatement<'a>,
+}
+impl PrettyPrint for WhileStatement<'_> {
+    fn pretty_print(&self) -> String {
+        "while ".to_owned()
+            + &self.condition.pretty_print()
+            + "\n"
+            + &indent(self.body.pretty_print())
+            + "\nendwhile"
+    }
+}
+impl AstNode for WhileStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Unlike a `WhileStatement` the body is always run at least once, and the
+/// loop stops once the condition is true rather than false.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct DoUntilStatement<'a> {
+    pub token:     Token<'a>,
+    pub span:      Span,
+    pub body:      BlockStatement<'a>,
+    pub condition: Box<Expr<'a>>,
+}
+impl PrettyPrint for DoUntilStatement<'_> {
+    fn pretty_print(&self) -> String {
+        "do\n".to_owned()
+            + &indent(self.body.pretty_print())
+            + "\nuntil "
+            + &self.condition.pretty_print()
+    }
+}
+impl AstNode for DoUntilStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct SwitchStatement<'a> {
+    pub token:   Token<'a>,
+    pub span:    Span,
+    pub subject: Box<Expr<'a>>,
+    /// In the order they were written, only the first matching case is run
+    pub cases:   Vec<SwitchCase<'a>>,
+    pub default: Option<BlockStatement<'a>>,
+}
+impl PrettyPrint for SwitchStatement<'_> {
+    fn pretty_print(&self) -> String {
+        "switch ".to_owned()
+            + &self.subject.pretty_print()
+            + ":\n"
+            + &self
+                .cases
+                .iter()
+                .map(|c| indent(c.pretty_print()) + "\n")
+                .collect::<String>()
+            + &match &self.default {
+                Some(d) => indent("default:\n".to_owned() + &indent(d.pretty_print())) + "\n",
+                None => String::new(),
+            }
+            + "endswitch"
+    }
+}
+impl AstNode for SwitchStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct SwitchCase<'a> {
+    pub token: Token<'a>,
+    pub span:  Span,
+    pub value: Box<Expr<'a>>,
+    pub body:  BlockStatement<'a>,
+}
+impl PrettyPrint for SwitchCase<'_> {
+    fn pretty_print(&self) -> String {
+        "case ".to_owned() + &self.value.pretty_print() + ":\n" + &indent(self.body.pretty_print())
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct FunctionStatement<'a> {
     pub token:        Token<'a>,
+    pub span:         Span,
     pub ident:        Identifier<'a>,
-    pub params:       Vec<Identifier<'a>>,
+    pub params:       Vec<Parameter<'a>>,
     pub body:         BlockStatement<'a>,
     pub is_procedure: bool,
 }
@@ -46,11 +250,11 @@ impl PrettyPrint for FunctionStatement<'_> {
             + &self
                 .params
                 .iter()
-                .map(|p| p.get_ident())
-                .collect::<Vec<&str>>()
+                .map(|p| p.pretty_print())
+                .collect::<Vec<String>>()
                 .join(", ")
             + ")\n"
-            + &self.body.pretty_print()
+            + &indent(self.body.pretty_print())
             + "\n"
             + if self.is_procedure {
                 "endprocedure"
@@ -59,18 +263,136 @@ impl PrettyPrint for FunctionStatement<'_> {
             }
     }
 }
-impl AstNode for FunctionStatement<'_> {}
-impl Statement for FunctionStatement<'_> {
-    fn get_type(&self) -> StatementType {
-        StatementType::Function(self)
+impl AstNode for FunctionStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
     }
 }
 
-#[derive(Debug)]
+/// How an argument is given to a function, written after the parameter like
+/// `a:byRef`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PassBy {
+    /// The function gets its own copy of the argument
+    Value,
+    /// Anything the function assigns to the parameter is assigned to the
+    /// variable that was given as the argument
+    Reference,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct Parameter<'a> {
+    pub ident:   Identifier<'a>,
+    /// `None` if the parameter isn't annotated
+    pub pass_by: Option<PassBy>,
+}
+impl PrettyPrint for Parameter<'_> {
+    fn pretty_print(&self) -> String {
+        self.ident.get_ident().to_owned()
+            + match self.pass_by {
+                Some(PassBy::Value) => ":byVal",
+                Some(PassBy::Reference) => ":byRef",
+                None => "",
+            }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct ClassStatement<'a> {
+    pub token:      Token<'a>,
+    pub span:       Span,
+    pub ident:      Identifier<'a>,
+    /// The class after `inherits`, if there is one
+    pub parent:     Option<Identifier<'a>>,
+    pub attributes: Vec<ClassAttribute<'a>>,
+    pub methods:    Vec<ClassMethod<'a>>,
+}
+impl PrettyPrint for ClassStatement<'_> {
+    fn pretty_print(&self) -> String {
+        "class ".to_owned()
+            + self.ident.get_ident()
+            + &match &self.parent {
+                Some(p) => " inherits ".to_owned() + p.get_ident(),
+                None => String::new(),
+            }
+            + "\n"
+            + &self
+                .attributes
+                .iter()
+                .map(|a| indent(a.pretty_print()) + "\n")
+                .collect::<String>()
+            + &self
+                .methods
+                .iter()
+                .map(|m| indent(m.pretty_print()) + "\n")
+                .collect::<String>()
+            + "endclass"
+    }
+}
+impl AstNode for ClassStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Members of a class are public unless they are marked as `private`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Visibility {
+    Public,
+    Private,
+}
+impl Display for Visibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Public => "public",
+            Self::Private => "private",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct ClassAttribute<'a> {
+    pub visibility: Visibility,
+    pub ident:      Identifier<'a>,
+}
+impl PrettyPrint for ClassAttribute<'_> {
+    fn pretty_print(&self) -> String {
+        self.visibility.to_string() + " " + self.ident.get_ident()
+    }
+}
+
+/// The constructor is the method called `new`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct ClassMethod<'a> {
+    pub visibility: Visibility,
+    pub func:       FunctionStatement<'a>,
+}
+impl PrettyPrint for ClassMethod<'_> {
+    fn pretty_print(&self) -> String {
+        self.visibility.to_string() + " " + &self.func.pretty_print()
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct IfStatement<'a> {
     pub token:       Token<'a>,
-    pub condition:   Box<dyn Expression + 'a>,
+    pub span:        Span,
+    pub condition:   Box<Expr<'a>>,
     pub consequence: BlockStatement<'a>,
+    /// `elseif` clauses, in the order they are checked
+    pub elseifs:     Vec<(Box<Expr<'a>>, BlockStatement<'a>)>,
     pub alternative: Option<BlockStatement<'a>>,
 }
 impl PrettyPrint for IfStatement<'_> {
@@ -78,26 +400,38 @@ impl PrettyPrint for IfStatement<'_> {
         "if ".to_string()
             + &self.condition.pretty_print()
             + " then\n"
-            + &self.consequence.pretty_print()
+            + &indent(self.consequence.pretty_print())
+            + &self
+                .elseifs
+                .iter()
+                .map(|(c, b)| {
+                    "\nelseif ".to_owned()
+                        + &c.pretty_print()
+                        + " then\n"
+                        + &indent(b.pretty_print())
+                })
+                .collect::<String>()
             + &if let Some(a) = &self.alternative {
-                "else\n".to_owned() + &a.pretty_print()
+                "\nelse\n".to_owned() + &indent(a.pretty_print())
             } else {
                 String::new()
             }
-            + "endif"
+            + "\nendif"
     }
 }
-impl AstNode for IfStatement<'_> {}
-impl Statement for IfStatement<'_> {
-    fn get_type(&self) -> StatementType {
-        StatementType::If(self)
+impl AstNode for IfStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct BlockStatement<'a> {
     pub token:      Token<'a>,
-    pub statements: Vec<Box<dyn Statement + 'a>>,
+    pub span:       Span,
+    pub statements: Vec<Stmt<'a>>,
 }
 impl PrettyPrint for BlockStatement<'_> {
     fn pretty_print(&self) -> String {
@@ -108,40 +442,102 @@ impl PrettyPrint for BlockStatement<'_> {
             .join("\n")
     }
 }
-impl AstNode for BlockStatement<'_> {}
-impl Statement for BlockStatement<'_> {
-    fn get_type(&self) -> StatementType {
-        StatementType::Block(self)
+impl AstNode for BlockStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct AssignStatement<'a> {
     pub token:  Token<'a>,
-    pub ident:  Identifier<'a>,
+    pub span:   Span,
+    pub target: AssignTarget<'a>,
     pub global: bool,
-    pub value:  Box<dyn Expression + 'a>,
+    pub value:  Box<Expr<'a>>,
 }
 impl PrettyPrint for AssignStatement<'_> {
     fn pretty_print(&self) -> String {
         (if self.global { "global " } else { "" }.to_owned()
-            + self.ident.get_ident()
-            + "="
+            + &self.target.pretty_print()
+            + " = "
             + &self.value.pretty_print())
             .to_owned()
     }
 }
-impl AstNode for AssignStatement<'_> {}
-impl Statement for AssignStatement<'_> {
-    fn get_type(&self) -> StatementType {
-        StatementType::Assign(self)
+impl AstNode for AssignStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
     }
 }
 
-#[derive(Debug)]
+/// Whatever is on the left hand side of an `AssignStatement`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", bound(deserialize = "'de: 'a")))]
+pub enum AssignTarget<'a> {
+    Identifier(Identifier<'a>),
+    Index(IndexExpression<'a>),
+    Member(MemberAccessExpression<'a>),
+}
+impl AssignTarget<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Identifier(i) => i.span,
+            Self::Index(i) => i.span,
+            Self::Member(m) => m.span,
+        }
+    }
+}
+impl PrettyPrint for AssignTarget<'_> {
+    fn pretty_print(&self) -> String {
+        match self {
+            Self::Identifier(i) => i.pretty_print(),
+            Self::Index(i) => i.pretty_print(),
+            Self::Member(m) => m.pretty_print(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct ArrayDeclarationStatement<'a> {
+    pub token: Token<'a>,
+    pub span:  Span,
+    pub ident: Identifier<'a>,
+    /// One size for a 1D array, two for a 2D array
+    pub sizes: Vec<Expr<'a>>,
+}
+impl PrettyPrint for ArrayDeclarationStatement<'_> {
+    fn pretty_print(&self) -> String {
+        "array ".to_owned()
+            + self.ident.get_ident()
+            + "["
+            + &self
+                .sizes
+                .iter()
+                .map(|s| s.pretty_print())
+                .collect::<Vec<String>>()
+                .join(", ")
+            + "]"
+    }
+}
+impl AstNode for ArrayDeclarationStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct ReturnStatement<'a> {
     pub token: Token<'a>,
-    pub value: Option<Box<dyn Expression + 'a>>,
+    pub span:  Span,
+    pub value: Option<Box<Expr<'a>>>,
 }
 impl PrettyPrint for ReturnStatement<'_> {
     fn pretty_print(&self) -> String {
@@ -152,73 +548,150 @@ impl PrettyPrint for ReturnStatement<'_> {
         .to_owned()
     }
 }
-impl AstNode for ReturnStatement<'_> {}
-impl Statement for ReturnStatement<'_> {
-    fn get_type(&self) -> StatementType {
-        StatementType::Return(self)
+impl AstNode for ReturnStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct ExpressionStatement<'a> {
-    pub value: Box<dyn Expression + 'a>,
+    pub span:  Span,
+    pub value: Box<Expr<'a>>,
 }
 impl PrettyPrint for ExpressionStatement<'_> {
     fn pretty_print(&self) -> String {
         self.value.pretty_print()
     }
 }
-impl AstNode for ExpressionStatement<'_> {}
-impl Statement for ExpressionStatement<'_> {
-    fn get_type(&self) -> StatementType {
-        StatementType::Expression(self)
+impl AstNode for ExpressionStatement<'_> {
+    fn span(&self) -> Span {
+        self.span
     }
 }
 
-#[derive(Debug)]
-pub struct EmptyStatement {}
-impl PrettyPrint for EmptyStatement {
+/// Stands in for a statement which failed to parse, when parsing with error
+/// recovery. The span covers the tokens skipped over, from the start of the
+/// statement to where the parser carried on from.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorStatement {
+    pub span: Span,
+}
+impl PrettyPrint for ErrorStatement {
     fn pretty_print(&self) -> String {
-        String::new()
+        "<ERROR_STATEMENT>".to_owned()
     }
 }
-impl AstNode for EmptyStatement {}
-impl Statement for EmptyStatement {
-    fn get_type(&self) -> StatementType {
-        StatementType::Empty
+impl AstNode for ErrorStatement {
+    fn span(&self) -> Span {
+        self.span
     }
 }
 
-pub enum ExpressionType<'a> {
-    Identifier(&'a Identifier<'a>),
-    Boolean(&'a BooleanExpression<'a>),
-    Placeholder(&'a PlaceholderExpression),
-    IntegerLiteral(&'a IntegerLiteralExpression<'a>),
-    Prefix(&'a PrefixExpression<'a>),
-    Infix(&'a InfixExpression<'a>),
-    FunctionCall(&'a FunctionCallExpression<'a>),
+/// An expression, which owns the node for whichever kind of expression it is
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", bound(deserialize = "'de: 'a")))]
+pub enum Expr<'a> {
+    Identifier(Identifier<'a>),
+    Boolean(BooleanExpression<'a>),
+    Placeholder(PlaceholderExpression),
+    IntegerLiteral(IntegerLiteralExpression<'a>),
+    RealLiteral(RealLiteralExpression<'a>),
+    StringLiteral(StringLiteralExpression<'a>),
+    Prefix(PrefixExpression<'a>),
+    Infix(InfixExpression<'a>),
+    FunctionCall(FunctionCallExpression<'a>),
+    Index(IndexExpression<'a>),
+    New(NewExpression<'a>),
+    Super(SuperExpression<'a>),
+    MemberAccess(MemberAccessExpression<'a>),
+    MethodCall(MethodCallExpression<'a>),
 }
+impl Expr<'_> {
+    /// How tightly the expression binds, anything other than an operator is
+    /// as tight as a call
+    pub(crate) fn precedence(&self) -> Precedence {
+        match self {
+            Self::Infix(e) => e.token.into(),
+            Self::Prefix(_) => Precedence::Prefix,
+            _ => Precedence::Call,
+        }
+    }
+
+    /// The expression, in brackets if they are needed to keep it together
+    fn pretty_print_bracketed_if(&self, needed: bool) -> String {
+        if needed {
+            "(".to_owned() + &self.pretty_print() + ")"
+        } else {
+            self.pretty_print()
+        }
+    }
 
-pub trait Expression: AstNode {
-    fn get_type(&self) -> ExpressionType;
     /// Instead of `1 + 2 * 3` will give `(1 + (2 * 3))`
-    fn pretty_print_with_brackets(&self) -> String {
-        self.pretty_print()
+    pub fn pretty_print_with_brackets(&self) -> String {
+        match self {
+            Self::Prefix(e) => e.pretty_print_with_brackets(),
+            Self::Infix(e) => e.pretty_print_with_brackets(),
+            Self::Index(e) => e.pretty_print_with_brackets(),
+            Self::MemberAccess(e) => e.pretty_print_with_brackets(),
+            Self::MethodCall(e) => e.pretty_print_with_brackets(),
+            e => e.pretty_print(),
+        }
     }
 }
-impl Default for Box<dyn Expression> {
-    fn default() -> Self {
-        Box::new(Identifier {
-            token: Token::Identifier("lol"),
-        })
+impl PrettyPrint for Expr<'_> {
+    fn pretty_print(&self) -> String {
+        match self {
+            Self::Identifier(e) => e.pretty_print(),
+            Self::Boolean(e) => e.pretty_print(),
+            Self::Placeholder(e) => e.pretty_print(),
+            Self::IntegerLiteral(e) => e.pretty_print(),
+            Self::RealLiteral(e) => e.pretty_print(),
+            Self::StringLiteral(e) => e.pretty_print(),
+            Self::Prefix(e) => e.pretty_print(),
+            Self::Infix(e) => e.pretty_print(),
+            Self::FunctionCall(e) => e.pretty_print(),
+            Self::Index(e) => e.pretty_print(),
+            Self::New(e) => e.pretty_print(),
+            Self::Super(e) => e.pretty_print(),
+            Self::MemberAccess(e) => e.pretty_print(),
+            Self::MethodCall(e) => e.pretty_print(),
+        }
+    }
+}
+impl AstNode for Expr<'_> {
+    fn span(&self) -> Span {
+        match self {
+            Self::Identifier(e) => e.span(),
+            Self::Boolean(e) => e.span(),
+            Self::Placeholder(e) => e.span(),
+            Self::IntegerLiteral(e) => e.span(),
+            Self::RealLiteral(e) => e.span(),
+            Self::StringLiteral(e) => e.span(),
+            Self::Prefix(e) => e.span(),
+            Self::Infix(e) => e.span(),
+            Self::FunctionCall(e) => e.span(),
+            Self::Index(e) => e.span(),
+            Self::New(e) => e.span(),
+            Self::Super(e) => e.span(),
+            Self::MemberAccess(e) => e.span(),
+            Self::MethodCall(e) => e.span(),
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct FunctionCallExpression<'a> {
     pub token: Token<'a>,
+    pub span:  Span,
     pub func:  Identifier<'a>,
-    pub args:  Vec<Box<dyn Expression + 'a>>,
+    pub args:  Vec<Expr<'a>>,
 }
 impl PrettyPrint for FunctionCallExpression<'_> {
     fn pretty_print(&self) -> String {
@@ -233,21 +706,195 @@ impl PrettyPrint for FunctionCallExpression<'_> {
             + ")"
     }
 }
-impl AstNode for FunctionCallExpression<'_> {}
-impl Expression for FunctionCallExpression<'_> {
-    fn get_type(&self) -> ExpressionType {
-        ExpressionType::FunctionCall(self)
+impl AstNode for FunctionCallExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// `names[3]` or `board[1, 2]`, the token is the `[`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct IndexExpression<'a> {
+    pub token:   Token<'a>,
+    pub span:    Span,
+    pub subject: Box<Expr<'a>>,
+    pub indices: Vec<Expr<'a>>,
+}
+impl PrettyPrint for IndexExpression<'_> {
+    fn pretty_print(&self) -> String {
+        self.subject
+            .pretty_print_bracketed_if(self.subject.precedence() < Precedence::Call)
+            + "["
+            + &self
+                .indices
+                .iter()
+                .map(|i| i.pretty_print())
+                .collect::<Vec<String>>()
+                .join(", ")
+            + "]"
+    }
+}
+impl AstNode for IndexExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+impl IndexExpression<'_> {
+    pub fn pretty_print_with_brackets(&self) -> String {
+        self.subject.pretty_print_with_brackets()
+            + "["
+            + &self
+                .indices
+                .iter()
+                .map(|i| i.pretty_print_with_brackets())
+                .collect::<Vec<String>>()
+                .join(", ")
+            + "]"
+    }
+}
+
+/// `new Dog("Rex")`, the token is the `new`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct NewExpression<'a> {
+    pub token: Token<'a>,
+    pub span:  Span,
+    pub class: Identifier<'a>,
+    pub args:  Vec<Expr<'a>>,
+}
+impl PrettyPrint for NewExpression<'_> {
+    fn pretty_print(&self) -> String {
+        "new ".to_owned()
+            + self.class.get_ident()
+            + "("
+            + &self
+                .args
+                .iter()
+                .map(|a| a.pretty_print())
+                .collect::<Vec<String>>()
+                .join(", ")
+            + ")"
+    }
+}
+impl AstNode for NewExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Only has a meaning as the subject of a method call, like `super.new()`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct SuperExpression<'a> {
+    pub token: Token<'a>,
+    pub span:  Span,
+}
+impl PrettyPrint for SuperExpression<'_> {
+    fn pretty_print(&self) -> String {
+        "super".to_owned()
+    }
+}
+impl AstNode for SuperExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// `pet.name`, the token is the `.`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct MemberAccessExpression<'a> {
+    pub token:   Token<'a>,
+    pub span:    Span,
+    pub subject: Box<Expr<'a>>,
+    pub member:  Identifier<'a>,
+}
+impl PrettyPrint for MemberAccessExpression<'_> {
+    fn pretty_print(&self) -> String {
+        self.subject
+            .pretty_print_bracketed_if(self.subject.precedence() < Precedence::Call)
+            + "."
+            + self.member.get_ident()
+    }
+}
+impl AstNode for MemberAccessExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+impl MemberAccessExpression<'_> {
+    pub fn pretty_print_with_brackets(&self) -> String {
+        self.subject.pretty_print_with_brackets() + "." + self.member.get_ident()
+    }
+}
+
+/// `pet.speak("woof")`, the token is the `.`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct MethodCallExpression<'a> {
+    pub token:   Token<'a>,
+    pub span:    Span,
+    pub subject: Box<Expr<'a>>,
+    pub method:  Identifier<'a>,
+    pub args:    Vec<Expr<'a>>,
+}
+impl PrettyPrint for MethodCallExpression<'_> {
+    fn pretty_print(&self) -> String {
+        self.subject
+            .pretty_print_bracketed_if(self.subject.precedence() < Precedence::Call)
+            + "."
+            + self.method.get_ident()
+            + "("
+            + &self
+                .args
+                .iter()
+                .map(|a| a.pretty_print())
+                .collect::<Vec<String>>()
+                .join(", ")
+            + ")"
+    }
+}
+impl AstNode for MethodCallExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+impl MethodCallExpression<'_> {
+    pub fn pretty_print_with_brackets(&self) -> String {
+        self.subject.pretty_print_with_brackets()
+            + "."
+            + self.method.get_ident()
+            + "("
+            + &self
+                .args
+                .iter()
+                .map(|a| a.pretty_print_with_brackets())
+                .collect::<Vec<String>>()
+                .join(", ")
+            + ")"
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct Identifier<'a> {
     /// Will always be `Token::Ident`
     pub token: Token<'a>,
+    pub span:  Span,
 }
-impl<'a> From<Token<'a>> for Identifier<'a> {
-    fn from(value: Token<'a>) -> Self {
-        Self { token: value }
+impl<'a> From<SpannedToken<'a>> for Identifier<'a> {
+    fn from(value: SpannedToken<'a>) -> Self {
+        Self {
+            token: value.tok,
+            span:  value.span,
+        }
     }
 }
 impl Identifier<'_> {
@@ -263,14 +910,14 @@ impl PrettyPrint for Identifier<'_> {
         self.get_ident().to_owned()
     }
 }
-impl AstNode for Identifier<'_> {}
-impl Expression for Identifier<'_> {
-    fn get_type(&self) -> ExpressionType {
-        ExpressionType::Identifier(self)
+impl AstNode for Identifier<'_> {
+    fn span(&self) -> Span {
+        self.span
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InfixOperator {
     Plus,
     Minus,
@@ -278,12 +925,14 @@ pub enum InfixOperator {
     Div,
     Mod,
     Multiply,
+    Power,
     DoubleEquals,
     NotEqual,
     LThan,
     LThanOrEqual,
     GThanOrEqual,
     GThan,
+    And,
     Or,
     LParenthasis, // Sneaky trick for function calls
 }
@@ -300,12 +949,14 @@ impl<'a> TryFrom<Token<'a>> for InfixOperator {
             Div => Ok(Self::Div),
             Mod => Ok(Self::Mod),
             Asterisk => Ok(Self::Multiply),
+            Caret => Ok(Self::Power),
             DoubleEquals => Ok(Self::DoubleEquals),
             LThan => Ok(Self::LThan),
             LThanOrEqual => Ok(Self::LThanOrEqual),
             GThan => Ok(Self::GThan),
             GThanOrEqual => Ok(Self::GThanOrEqual),
             NotEqual => Ok(Self::NotEqual),
+            And => Ok(Self::And),
             Or => Ok(Self::Or),
             LParenthasis => Ok(Self::LParenthasis),
             _ => Err(NoSuchInfixOperatorError { tok: value }),
@@ -317,11 +968,13 @@ impl Display for InfixOperator {
         write!(f, "{}", match self {
             Self::Div => " DIV ",
             Self::Mod => " MOD ",
+            Self::And => " AND ",
             Self::Or => " OR ",
             Self::Plus => "+",
             Self::Minus => "-",
             Self::Divide => "/",
             Self::Multiply => "*",
+            Self::Power => "^",
             Self::DoubleEquals => "==",
             Self::LThanOrEqual => "<=",
             Self::LThan => "<",
@@ -337,25 +990,44 @@ pub struct NoSuchInfixOperatorError<'a> {
     pub tok: Token<'a>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct InfixExpression<'a> {
     pub token:    Token<'a>,
+    pub span:     Span,
     pub operator: InfixOperator,
-    pub left:     Box<dyn Expression + 'a>,
-    pub right:    Box<dyn Expression + 'a>,
+    pub left:     Box<Expr<'a>>,
+    pub right:    Box<Expr<'a>>,
 }
 impl PrettyPrint for InfixExpression<'_> {
+    /// Brackets are only kept where the tree wouldn't parse the same without
+    /// them, so `(a + b) * c` keeps them but `a + (b * c)` doesn't
     fn pretty_print(&self) -> String {
-        self.left.pretty_print() + &self.operator.to_string() + &self.right.pretty_print()
+        let prec = Precedence::from(self.token);
+        // `^` is right associative, every other operator is left associative
+        let right_assoc = self.operator == InfixOperator::Power;
+        let left = self.left.precedence();
+        let right = self.right.precedence();
+        // A prefix operator on the right takes in everything after it anyway
+        let right_prefix = matches!(*self.right, Expr::Prefix(_));
+        self.left
+            .pretty_print_bracketed_if(left < prec || (right_assoc && left == prec))
+            + " "
+            + self.operator.to_string().trim()
+            + " "
+            + &self.right.pretty_print_bracketed_if(
+                !right_prefix && (right < prec || (!right_assoc && right == prec)),
+            )
     }
 }
-impl AstNode for InfixExpression<'_> {}
-impl Expression for InfixExpression<'_> {
-    fn get_type(&self) -> ExpressionType {
-        ExpressionType::Infix(self)
+impl AstNode for InfixExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
     }
-
-    fn pretty_print_with_brackets(&self) -> String {
+}
+impl InfixExpression<'_> {
+    pub fn pretty_print_with_brackets(&self) -> String {
         "(".to_owned()
             + &self.left.pretty_print_with_brackets()
             + &self.operator.to_string()
@@ -364,7 +1036,8 @@ impl Expression for InfixExpression<'_> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PrefixOperator {
     Plus,
     Minus,
@@ -392,33 +1065,37 @@ impl Display for PrefixOperator {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct PrefixExpression<'a> {
     pub token:    Token<'a>,
+    pub span:     Span,
     pub operator: PrefixOperator,
-    pub subject:  Box<dyn Expression + 'a>,
+    pub subject:  Box<Expr<'a>>,
 }
 impl PrettyPrint for PrefixExpression<'_> {
     fn pretty_print(&self) -> String {
-        self.operator.to_string() + &self.subject.pretty_print()
+        self.operator.to_string()
+            + &self
+                .subject
+                .pretty_print_bracketed_if(self.subject.precedence() < Precedence::Prefix)
     }
 }
-impl AstNode for PrefixExpression<'_> {}
-impl Expression for PrefixExpression<'_> {
-    fn get_type(&self) -> ExpressionType {
-        ExpressionType::Prefix(self)
+impl AstNode for PrefixExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
     }
-
-    fn pretty_print_with_brackets(&self) -> String {
+}
+impl PrefixExpression<'_> {
+    pub fn pretty_print_with_brackets(&self) -> String {
         // This has to be done manually else brackets wont be put around the prefix's
         // subject
-        match self.subject.get_type() {
-            ExpressionType::Infix(_) => {
+        match *self.subject {
+            Expr::Infix(_) => {
                 "(".to_owned()
                     + &self.operator.to_string()
-                    + "("
-                    + &self.subject.pretty_print()
-                    + ")"
+                    + &self.subject.pretty_print_with_brackets()
                     + ")"
             }
             _ => "(".to_owned() + &self.pretty_print() + ")",
@@ -426,40 +1103,97 @@ impl Expression for PrefixExpression<'_> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct IntegerLiteralExpression<'a> {
     pub token: Token<'a>,
+    pub span:  Span,
+    #[cfg_attr(feature = "serde", serde(with = "crate::json::integer"))]
     pub value: i128,
 }
+/// Literals are printed as they were written, like `007`, unless the value
+/// has been changed since it was parsed
 impl PrettyPrint for IntegerLiteralExpression<'_> {
     fn pretty_print(&self) -> String {
-        format!("{}", self.value)
+        match self.token {
+            Token::NumberLiteral(text) if text.parse() == Ok(self.value) => text.to_owned(),
+            _ => format!("{}", self.value),
+        }
     }
 }
-impl AstNode for IntegerLiteralExpression<'_> {}
-impl Expression for IntegerLiteralExpression<'_> {
-    fn get_type(&self) -> ExpressionType {
-        ExpressionType::IntegerLiteral(self)
+impl AstNode for IntegerLiteralExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct RealLiteralExpression<'a> {
+    pub token: Token<'a>,
+    pub span:  Span,
+    pub value: f64,
+}
+/// Literals are printed as they were written, like `1.5e3`, unless the value
+/// has been changed since it was parsed
+impl PrettyPrint for RealLiteralExpression<'_> {
+    fn pretty_print(&self) -> String {
+        match self.token {
+            Token::NumberLiteral(text) if text.parse() == Ok(self.value) => text.to_owned(),
+            // Debug formatting always keeps the decimal point, so the value can't be
+            // mistaken for an integer when it is parsed again
+            _ => format!("{:?}", self.value),
+        }
+    }
+}
+impl AstNode for RealLiteralExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// The value is the text between the quotes, there are no escape sequences
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct StringLiteralExpression<'a> {
+    pub token: Token<'a>,
+    pub span:  Span,
+    pub value: &'a str,
+}
+impl PrettyPrint for StringLiteralExpression<'_> {
+    fn pretty_print(&self) -> String {
+        format!("\"{}\"", self.value)
+    }
+}
+impl AstNode for StringLiteralExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlaceholderExpression {}
 impl PrettyPrint for PlaceholderExpression {
     fn pretty_print(&self) -> String {
         "<PLACEHOLDER_EXPRESSION>".to_owned()
     }
 }
-impl AstNode for PlaceholderExpression {}
-impl Expression for PlaceholderExpression {
-    fn get_type(&self) -> ExpressionType {
-        ExpressionType::Placeholder(self)
+impl AstNode for PlaceholderExpression {
+    fn span(&self) -> Span {
+        Span::default()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct BooleanExpression<'a> {
     pub token: Token<'a>,
+    pub span:  Span,
     pub value: bool,
 }
 impl PrettyPrint for BooleanExpression<'_> {
@@ -467,9 +1201,57 @@ impl PrettyPrint for BooleanExpression<'_> {
         if self.value { "true" } else { "false" }.to_owned()
     }
 }
-impl AstNode for BooleanExpression<'_> {}
-impl Expression for BooleanExpression<'_> {
-    fn get_type(&self) -> ExpressionType {
-        ExpressionType::Boolean(self)
+impl AstNode for BooleanExpression<'_> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Nodes are compared by everything but their spans, so that the same code
+/// written with different spacing gives equal trees. Every other field has to
+/// be listed, which the pattern checks.
+macro_rules! impl_eq_ignoring_span {
+    ($($node:ident { $($field:ident),* })*) => {$(
+        impl PartialEq for $node<'_> {
+            fn eq(&self, other: &Self) -> bool {
+                let Self { span: _, $($field: _),* } = self;
+                true $(&& self.$field == other.$field)*
+            }
+        }
+    )*};
+}
+impl_eq_ignoring_span! {
+    ForStatement { token, counter, start, end, step, body }
+    WhileStatement { token, condition, body }
+    DoUntilStatement { token, body, condition }
+    SwitchStatement { token, subject, cases, default }
+    SwitchCase { token, value, body }
+    FunctionStatement { token, ident, params, body, is_procedure }
+    ClassStatement { token, ident, parent, attributes, methods }
+    IfStatement { token, condition, consequence, elseifs, alternative }
+    BlockStatement { token, statements }
+    AssignStatement { token, target, global, value }
+    ArrayDeclarationStatement { token, ident, sizes }
+    ReturnStatement { token, value }
+    ExpressionStatement { value }
+    FunctionCallExpression { token, func, args }
+    IndexExpression { token, subject, indices }
+    NewExpression { token, class, args }
+    SuperExpression { token }
+    MemberAccessExpression { token, subject, member }
+    MethodCallExpression { token, subject, method, args }
+    Identifier { token }
+    InfixExpression { token, operator, left, right }
+    PrefixExpression { token, operator, subject }
+    IntegerLiteralExpression { token, value }
+    RealLiteralExpression { token, value }
+    StringLiteralExpression { token, value }
+    BooleanExpression { token, value }
+}
+
+/// Error statements are all equal, since the span is all they have
+impl PartialEq for ErrorStatement {
+    fn eq(&self, _other: &Self) -> bool {
+        true
     }
 }
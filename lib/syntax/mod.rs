@@ -1,6 +1,11 @@
 mod ast;
+pub mod owned;
+mod visit;
 
 #[cfg(test)]
 mod test;
 
 pub use ast::*;
+pub use owned::AsBorrowed;
+pub use owned::IntoOwned;
+pub use visit::*;
@@ -0,0 +1,589 @@
+//! An owned form of the AST, which holds its text in shared strings instead
+//! of borrowing it from the source. Every type here is the owned form of the
+//! node with the same name in the AST, with the same fields. None of them
+//! have a lifetime, so an owned `Program` can be kept around after the
+//! source is gone, cached or sent to another thread. To run or print it, it
+//! is borrowed back into an ordinary `Program` with `as_borrowed`.
+
+use std::sync::Arc;
+
+use super::ast;
+use super::ErrorStatement;
+use super::InfixOperator;
+use super::PassBy;
+use super::PlaceholderExpression;
+use super::PrefixOperator;
+use super::Visibility;
+use crate::lexer::Interner;
+use crate::lexer::OwnedToken;
+use crate::lexer::Span;
+use crate::lexer::Token;
+use crate::parser;
+
+/// Converts an AST node into its owned form, by copying the text of every
+/// token and string in it into the interner.
+pub trait IntoOwned {
+    type Owned: 'static;
+
+    fn into_owned(self, interner: &Interner) -> Self::Owned;
+}
+
+/// Borrows an owned AST node as an ordinary one, whose text borrows from the
+/// owned node.
+pub trait AsBorrowed {
+    type Borrowed<'o>
+    where
+        Self: 'o;
+
+    fn as_borrowed(&self) -> Self::Borrowed<'_>;
+}
+
+impl IntoOwned for Token<'_> {
+    type Owned = OwnedToken;
+
+    fn into_owned(self, interner: &Interner) -> OwnedToken {
+        Token::into_owned(self, interner)
+    }
+}
+impl AsBorrowed for OwnedToken {
+    type Borrowed<'o> = Token<'o>;
+
+    fn as_borrowed(&self) -> Token<'_> {
+        self.as_token()
+    }
+}
+
+impl IntoOwned for &str {
+    type Owned = Arc<str>;
+
+    fn into_owned(self, interner: &Interner) -> Arc<str> {
+        interner.intern(self)
+    }
+}
+impl AsBorrowed for Arc<str> {
+    type Borrowed<'o> = &'o str;
+
+    fn as_borrowed(&self) -> &str {
+        self
+    }
+}
+
+/// Fields without any text are the same in both forms
+macro_rules! impl_unchanged {
+    ($($ty:ty),*) => {$(
+        impl IntoOwned for $ty {
+            type Owned = $ty;
+
+            fn into_owned(self, _: &Interner) -> $ty {
+                self
+            }
+        }
+        impl AsBorrowed for $ty {
+            type Borrowed<'o> = $ty;
+
+            fn as_borrowed(&self) -> $ty {
+                self.clone()
+            }
+        }
+    )*};
+}
+
+impl_unchanged!(
+    Span,
+    bool,
+    i128,
+    f64,
+    PassBy,
+    Visibility,
+    InfixOperator,
+    PrefixOperator,
+    ErrorStatement,
+    PlaceholderExpression
+);
+
+impl<T: IntoOwned> IntoOwned for Box<T> {
+    type Owned = Box<T::Owned>;
+
+    fn into_owned(self, interner: &Interner) -> Box<T::Owned> {
+        Box::new((*self).into_owned(interner))
+    }
+}
+impl<T: AsBorrowed> AsBorrowed for Box<T> {
+    type Borrowed<'o>
+        = Box<T::Borrowed<'o>>
+    where
+        T: 'o;
+
+    fn as_borrowed(&self) -> Box<T::Borrowed<'_>> {
+        Box::new((**self).as_borrowed())
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Vec<T> {
+    type Owned = Vec<T::Owned>;
+
+    fn into_owned(self, interner: &Interner) -> Vec<T::Owned> {
+        self.into_iter().map(|n| n.into_owned(interner)).collect()
+    }
+}
+impl<T: AsBorrowed> AsBorrowed for Vec<T> {
+    type Borrowed<'o>
+        = Vec<T::Borrowed<'o>>
+    where
+        T: 'o;
+
+    fn as_borrowed(&self) -> Vec<T::Borrowed<'_>> {
+        self.iter().map(T::as_borrowed).collect()
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Option<T> {
+    type Owned = Option<T::Owned>;
+
+    fn into_owned(self, interner: &Interner) -> Option<T::Owned> {
+        self.map(|n| n.into_owned(interner))
+    }
+}
+impl<T: AsBorrowed> AsBorrowed for Option<T> {
+    type Borrowed<'o>
+        = Option<T::Borrowed<'o>>
+    where
+        T: 'o;
+
+    fn as_borrowed(&self) -> Option<T::Borrowed<'_>> {
+        self.as_ref().map(T::as_borrowed)
+    }
+}
+
+impl<A: IntoOwned, B: IntoOwned> IntoOwned for (A, B) {
+    type Owned = (A::Owned, B::Owned);
+
+    fn into_owned(self, interner: &Interner) -> (A::Owned, B::Owned) {
+        (self.0.into_owned(interner), self.1.into_owned(interner))
+    }
+}
+impl<A: AsBorrowed, B: AsBorrowed> AsBorrowed for (A, B) {
+    type Borrowed<'o>
+        = (A::Borrowed<'o>, B::Borrowed<'o>)
+    where
+        Self: 'o;
+
+    fn as_borrowed(&self) -> Self::Borrowed<'_> {
+        (self.0.as_borrowed(), self.1.as_borrowed())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Program {
+    pub statements: Vec<Stmt>,
+}
+impl IntoOwned for parser::Program<'_> {
+    type Owned = Program;
+
+    fn into_owned(self, interner: &Interner) -> Program {
+        Program {
+            statements: self.statements.into_owned(interner),
+        }
+    }
+}
+impl AsBorrowed for Program {
+    type Borrowed<'o> = parser::Program<'o>;
+
+    fn as_borrowed(&self) -> parser::Program<'_> {
+        parser::Program {
+            statements: self.statements.as_borrowed(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Assign(AssignStatement),
+    Return(ReturnStatement),
+    Expression(ExpressionStatement),
+    If(IfStatement),
+    Block(BlockStatement),
+    Function(FunctionStatement),
+    For(ForStatement),
+    While(WhileStatement),
+    DoUntil(DoUntilStatement),
+    Switch(SwitchStatement),
+    Error(ErrorStatement),
+    ArrayDeclaration(ArrayDeclarationStatement),
+    Class(ClassStatement),
+    Empty,
+}
+impl IntoOwned for ast::Stmt<'_> {
+    type Owned = Stmt;
+
+    fn into_owned(self, interner: &Interner) -> Stmt {
+        use ast::Stmt::*;
+
+        match self {
+            Assign(n) => Stmt::Assign(n.into_owned(interner)),
+            Return(n) => Stmt::Return(n.into_owned(interner)),
+            Expression(n) => Stmt::Expression(n.into_owned(interner)),
+            If(n) => Stmt::If(n.into_owned(interner)),
+            Block(n) => Stmt::Block(n.into_owned(interner)),
+            Function(n) => Stmt::Function(n.into_owned(interner)),
+            For(n) => Stmt::For(n.into_owned(interner)),
+            While(n) => Stmt::While(n.into_owned(interner)),
+            DoUntil(n) => Stmt::DoUntil(n.into_owned(interner)),
+            Switch(n) => Stmt::Switch(n.into_owned(interner)),
+            Error(n) => Stmt::Error(n),
+            ArrayDeclaration(n) => Stmt::ArrayDeclaration(n.into_owned(interner)),
+            Class(n) => Stmt::Class(n.into_owned(interner)),
+            Empty => Stmt::Empty,
+        }
+    }
+}
+impl AsBorrowed for Stmt {
+    type Borrowed<'o> = ast::Stmt<'o>;
+
+    fn as_borrowed(&self) -> ast::Stmt<'_> {
+        use ast::Stmt::*;
+
+        match self {
+            Self::Assign(n) => Assign(n.as_borrowed()),
+            Self::Return(n) => Return(n.as_borrowed()),
+            Self::Expression(n) => Expression(n.as_borrowed()),
+            Self::If(n) => If(n.as_borrowed()),
+            Self::Block(n) => Block(n.as_borrowed()),
+            Self::Function(n) => Function(n.as_borrowed()),
+            Self::For(n) => For(n.as_borrowed()),
+            Self::While(n) => While(n.as_borrowed()),
+            Self::DoUntil(n) => DoUntil(n.as_borrowed()),
+            Self::Switch(n) => Switch(n.as_borrowed()),
+            Self::Error(n) => Error(n.clone()),
+            Self::ArrayDeclaration(n) => ArrayDeclaration(n.as_borrowed()),
+            Self::Class(n) => Class(n.as_borrowed()),
+            Self::Empty => Empty,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssignTarget {
+    Identifier(Identifier),
+    Index(IndexExpression),
+    Member(MemberAccessExpression),
+}
+impl IntoOwned for ast::AssignTarget<'_> {
+    type Owned = AssignTarget;
+
+    fn into_owned(self, interner: &Interner) -> AssignTarget {
+        use ast::AssignTarget::*;
+
+        match self {
+            Identifier(n) => AssignTarget::Identifier(n.into_owned(interner)),
+            Index(n) => AssignTarget::Index(n.into_owned(interner)),
+            Member(n) => AssignTarget::Member(n.into_owned(interner)),
+        }
+    }
+}
+impl AsBorrowed for AssignTarget {
+    type Borrowed<'o> = ast::AssignTarget<'o>;
+
+    fn as_borrowed(&self) -> ast::AssignTarget<'_> {
+        use ast::AssignTarget::*;
+
+        match self {
+            Self::Identifier(n) => Identifier(n.as_borrowed()),
+            Self::Index(n) => Index(n.as_borrowed()),
+            Self::Member(n) => Member(n.as_borrowed()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Identifier(Identifier),
+    Boolean(BooleanExpression),
+    Placeholder(PlaceholderExpression),
+    IntegerLiteral(IntegerLiteralExpression),
+    RealLiteral(RealLiteralExpression),
+    StringLiteral(StringLiteralExpression),
+    Prefix(PrefixExpression),
+    Infix(InfixExpression),
+    FunctionCall(FunctionCallExpression),
+    Index(IndexExpression),
+    New(NewExpression),
+    Super(SuperExpression),
+    MemberAccess(MemberAccessExpression),
+    MethodCall(MethodCallExpression),
+}
+impl IntoOwned for ast::Expr<'_> {
+    type Owned = Expr;
+
+    fn into_owned(self, interner: &Interner) -> Expr {
+        use ast::Expr::*;
+
+        match self {
+            Identifier(n) => Expr::Identifier(n.into_owned(interner)),
+            Boolean(n) => Expr::Boolean(n.into_owned(interner)),
+            Placeholder(n) => Expr::Placeholder(n),
+            IntegerLiteral(n) => Expr::IntegerLiteral(n.into_owned(interner)),
+            RealLiteral(n) => Expr::RealLiteral(n.into_owned(interner)),
+            StringLiteral(n) => Expr::StringLiteral(n.into_owned(interner)),
+            Prefix(n) => Expr::Prefix(n.into_owned(interner)),
+            Infix(n) => Expr::Infix(n.into_owned(interner)),
+            FunctionCall(n) => Expr::FunctionCall(n.into_owned(interner)),
+            Index(n) => Expr::Index(n.into_owned(interner)),
+            New(n) => Expr::New(n.into_owned(interner)),
+            Super(n) => Expr::Super(n.into_owned(interner)),
+            MemberAccess(n) => Expr::MemberAccess(n.into_owned(interner)),
+            MethodCall(n) => Expr::MethodCall(n.into_owned(interner)),
+        }
+    }
+}
+impl AsBorrowed for Expr {
+    type Borrowed<'o> = ast::Expr<'o>;
+
+    fn as_borrowed(&self) -> ast::Expr<'_> {
+        use ast::Expr::*;
+
+        match self {
+            Self::Identifier(n) => Identifier(n.as_borrowed()),
+            Self::Boolean(n) => Boolean(n.as_borrowed()),
+            Self::Placeholder(n) => Placeholder(n.clone()),
+            Self::IntegerLiteral(n) => IntegerLiteral(n.as_borrowed()),
+            Self::RealLiteral(n) => RealLiteral(n.as_borrowed()),
+            Self::StringLiteral(n) => StringLiteral(n.as_borrowed()),
+            Self::Prefix(n) => Prefix(n.as_borrowed()),
+            Self::Infix(n) => Infix(n.as_borrowed()),
+            Self::FunctionCall(n) => FunctionCall(n.as_borrowed()),
+            Self::Index(n) => Index(n.as_borrowed()),
+            Self::New(n) => New(n.as_borrowed()),
+            Self::Super(n) => Super(n.as_borrowed()),
+            Self::MemberAccess(n) => MemberAccess(n.as_borrowed()),
+            Self::MethodCall(n) => MethodCall(n.as_borrowed()),
+        }
+    }
+}
+
+/// Declares the owned form of an AST node, with the same fields as the node
+/// but owning their text, along with the conversions to and from it
+macro_rules! owned_node {
+    ($name:ident { $($field:ident: $ty:ty),* $(,)? }) => {
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $name {
+            $(pub $field: $ty,)*
+        }
+        impl IntoOwned for ast::$name<'_> {
+            type Owned = $name;
+
+            fn into_owned(self, interner: &Interner) -> $name {
+                $name {
+                    $($field: self.$field.into_owned(interner),)*
+                }
+            }
+        }
+        impl AsBorrowed for $name {
+            type Borrowed<'o> = ast::$name<'o>;
+
+            fn as_borrowed(&self) -> ast::$name<'_> {
+                ast::$name {
+                    $($field: self.$field.as_borrowed(),)*
+                }
+            }
+        }
+    };
+}
+
+owned_node!(ForStatement {
+    token:   OwnedToken,
+    span:    Span,
+    counter: Identifier,
+    start:   Box<Expr>,
+    end:     Box<Expr>,
+    step:    Option<Box<Expr>>,
+    body:    BlockStatement,
+});
+
+owned_node!(WhileStatement {
+    token:     OwnedToken,
+    span:      Span,
+    condition: Box<Expr>,
+    body:      BlockStatement,
+});
+
+owned_node!(DoUntilStatement {
+    token:     OwnedToken,
+    span:      Span,
+    body:      BlockStatement,
+    condition: Box<Expr>,
+});
+
+owned_node!(SwitchStatement {
+    token:   OwnedToken,
+    span:    Span,
+    subject: Box<Expr>,
+    cases:   Vec<SwitchCase>,
+    default: Option<BlockStatement>,
+});
+
+owned_node!(SwitchCase {
+    token: OwnedToken,
+    span:  Span,
+    value: Box<Expr>,
+    body:  BlockStatement,
+});
+
+owned_node!(FunctionStatement {
+    token:        OwnedToken,
+    span:         Span,
+    ident:        Identifier,
+    params:       Vec<Parameter>,
+    body:         BlockStatement,
+    is_procedure: bool,
+});
+
+owned_node!(Parameter {
+    ident:   Identifier,
+    pass_by: Option<PassBy>,
+});
+
+owned_node!(ClassStatement {
+    token:      OwnedToken,
+    span:       Span,
+    ident:      Identifier,
+    parent:     Option<Identifier>,
+    attributes: Vec<ClassAttribute>,
+    methods:    Vec<ClassMethod>,
+});
+
+owned_node!(ClassAttribute {
+    visibility: Visibility,
+    ident:      Identifier,
+});
+
+owned_node!(ClassMethod {
+    visibility: Visibility,
+    func:       FunctionStatement,
+});
+
+owned_node!(IfStatement {
+    token:       OwnedToken,
+    span:        Span,
+    condition:   Box<Expr>,
+    consequence: BlockStatement,
+    elseifs:     Vec<(Box<Expr>, BlockStatement)>,
+    alternative: Option<BlockStatement>,
+});
+
+owned_node!(BlockStatement {
+    token:      OwnedToken,
+    span:       Span,
+    statements: Vec<Stmt>,
+});
+
+owned_node!(AssignStatement {
+    token:  OwnedToken,
+    span:   Span,
+    target: AssignTarget,
+    global: bool,
+    value:  Box<Expr>,
+});
+
+owned_node!(ArrayDeclarationStatement {
+    token: OwnedToken,
+    span:  Span,
+    ident: Identifier,
+    sizes: Vec<Expr>,
+});
+
+owned_node!(ReturnStatement {
+    token: OwnedToken,
+    span:  Span,
+    value: Option<Box<Expr>>,
+});
+
+owned_node!(ExpressionStatement {
+    span:  Span,
+    value: Box<Expr>,
+});
+
+owned_node!(FunctionCallExpression {
+    token: OwnedToken,
+    span:  Span,
+    func:  Identifier,
+    args:  Vec<Expr>,
+});
+
+owned_node!(IndexExpression {
+    token:   OwnedToken,
+    span:    Span,
+    subject: Box<Expr>,
+    indices: Vec<Expr>,
+});
+
+owned_node!(NewExpression {
+    token: OwnedToken,
+    span:  Span,
+    class: Identifier,
+    args:  Vec<Expr>,
+});
+
+owned_node!(SuperExpression {
+    token: OwnedToken,
+    span:  Span,
+});
+
+owned_node!(MemberAccessExpression {
+    token:   OwnedToken,
+    span:    Span,
+    subject: Box<Expr>,
+    member:  Identifier,
+});
+
+owned_node!(MethodCallExpression {
+    token:   OwnedToken,
+    span:    Span,
+    subject: Box<Expr>,
+    method:  Identifier,
+    args:    Vec<Expr>,
+});
+
+owned_node!(Identifier {
+    token: OwnedToken,
+    span:  Span,
+});
+
+owned_node!(InfixExpression {
+    token:    OwnedToken,
+    span:     Span,
+    operator: InfixOperator,
+    left:     Box<Expr>,
+    right:    Box<Expr>,
+});
+
+owned_node!(PrefixExpression {
+    token:    OwnedToken,
+    span:     Span,
+    operator: PrefixOperator,
+    subject:  Box<Expr>,
+});
+
+owned_node!(IntegerLiteralExpression {
+    token: OwnedToken,
+    span:  Span,
+    value: i128,
+});
+
+owned_node!(RealLiteralExpression {
+    token: OwnedToken,
+    span:  Span,
+    value: f64,
+});
+
+owned_node!(StringLiteralExpression {
+    token: OwnedToken,
+    span:  Span,
+    value: Arc<str>,
+});
+
+owned_node!(BooleanExpression {
+    token: OwnedToken,
+    span:  Span,
+    value: bool,
+});
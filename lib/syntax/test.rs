@@ -1,9 +1,21 @@
 use super::ast::Identifier;
 use super::ast::PrettyPrint;
+use super::walk_expr_mut;
+use super::walk_function_call;
+use super::AsBorrowed;
 use super::BooleanExpression;
+use super::Expr;
+use super::FunctionCallExpression;
+use super::IntegerLiteralExpression;
+use super::IntoOwned;
 use super::PlaceholderExpression;
 use super::PrefixExpression;
+use super::Visitor;
+use super::VisitorMut;
+use crate::lexer::Interner;
+use crate::lexer::Span;
 use crate::lexer::Token;
+use crate::parser::parse_from_string;
 use crate::syntax::InfixExpression;
 use crate::syntax::InfixOperator;
 use crate::syntax::PrefixOperator;
@@ -12,6 +24,7 @@ use crate::syntax::PrefixOperator;
 fn test_pretty_print_identifiers() {
     let ident = Identifier {
         token: Token::Identifier("ItsGoodToBeD"),
+        span:  Span::default(),
     };
     assert_eq!(ident.pretty_print(), "ItsGoodToBeD".to_owned());
 }
@@ -19,23 +32,26 @@ fn test_pretty_print_identifiers() {
 #[test]
 fn test_pretty_print_prefix_op() {
     let op = PrefixExpression {
-        subject:  Box::new(PlaceholderExpression {}),
+        subject:  Box::new(Expr::Placeholder(PlaceholderExpression {})),
         operator: PrefixOperator::Minus,
         token:    Token::default(),
+        span:     Span::default(),
     };
     assert_eq!(op.pretty_print(), "-<PLACEHOLDER_EXPRESSION>");
 
     let op = PrefixExpression {
-        subject:  Box::new(PlaceholderExpression {}),
+        subject:  Box::new(Expr::Placeholder(PlaceholderExpression {})),
         operator: PrefixOperator::Not,
         token:    Token::default(),
+        span:     Span::default(),
     };
     assert_eq!(op.pretty_print(), "NOT <PLACEHOLDER_EXPRESSION>");
 
     let op = PrefixExpression {
-        subject:  Box::new(PlaceholderExpression {}),
+        subject:  Box::new(Expr::Placeholder(PlaceholderExpression {})),
         operator: PrefixOperator::Plus,
         token:    Token::default(),
+        span:     Span::default(),
     };
     assert_eq!(op.pretty_print(), "+<PLACEHOLDER_EXPRESSION>")
 }
@@ -44,13 +60,14 @@ fn test_pretty_print_prefix_op() {
 fn test_pretty_print_infix_op() {
     let op = InfixExpression {
         operator: InfixOperator::Divide,
-        left:     Box::new(PlaceholderExpression {}),
-        right:    Box::new(PlaceholderExpression {}),
+        left:     Box::new(Expr::Placeholder(PlaceholderExpression {})),
+        right:    Box::new(Expr::Placeholder(PlaceholderExpression {})),
         token:    Token::default(),
+        span:     Span::default(),
     };
     assert_eq!(
         op.pretty_print(),
-        "<PLACEHOLDER_EXPRESSION>/<PLACEHOLDER_EXPRESSION>"
+        "<PLACEHOLDER_EXPRESSION> / <PLACEHOLDER_EXPRESSION>"
     )
 }
 
@@ -59,6 +76,7 @@ fn test_pretty_print_bool_expression() {
     assert_eq!(
         BooleanExpression {
             token: Token::Eof,
+            span:  Span::default(),
             value: true,
         }
         .pretty_print(),
@@ -68,9 +86,172 @@ fn test_pretty_print_bool_expression() {
     assert_eq!(
         BooleanExpression {
             token: Token::Eof,
+            span:  Span::default(),
             value: false,
         }
         .pretty_print(),
         "false"
     );
 }
+
+#[test]
+fn test_visitor() {
+    #[derive(Default)]
+    struct Names<'a> {
+        calls:       Vec<&'a str>,
+        identifiers: Vec<&'a str>,
+    }
+    impl<'a> Visitor<'a> for Names<'a> {
+        fn visit_function_call(&mut self, expr: &'a FunctionCallExpression<'a>) {
+            self.calls.push(expr.func.get_ident());
+            walk_function_call(self, expr);
+        }
+
+        fn visit_identifier(&mut self, ident: &'a Identifier<'a>) {
+            self.identifiers.push(ident.get_ident());
+        }
+    }
+
+    let prog = parse_from_string(
+        "function double(n)
+    return n * 2
+endfunction
+x = double(y)
+if x > 1 then
+    print(x)
+elseif z then
+    array a[size(w)]
+endif
+for i = 1 to x
+next i
+class Dog inherits Pet
+    private name
+    public procedure bark(times)
+        print(name)
+    endprocedure
+endclass
+d = new Dog()
+d.bark(d.age)",
+    )
+    .unwrap();
+    let mut names = Names::default();
+    names.visit_program(&prog);
+    assert_eq!(names.calls, ["double", "print", "size", "print"]);
+    assert_eq!(names.identifiers, [
+        "double", "n", "n", "double", "y", "x", "x", "print", "x", "z", "a", "size", "w", "i", "x",
+        "Dog", "Pet", "name", "bark", "times", "print", "name", "Dog", "d", "d", "bark", "d",
+        "age"
+    ]);
+}
+
+#[test]
+fn test_visitor_mut() {
+    /// Replaces `<integer> + <integer>` with the result
+    struct FoldAdditions;
+    impl<'a> VisitorMut<'a> for FoldAdditions {
+        fn visit_expr_mut(&mut self, expr: &mut Expr<'a>) {
+            walk_expr_mut(self, expr);
+            if let Expr::Infix(InfixExpression {
+                operator: InfixOperator::Plus,
+                left,
+                right,
+                token,
+                span,
+            }) = expr
+            {
+                if let (Expr::IntegerLiteral(l), Expr::IntegerLiteral(r)) = (&**left, &**right) {
+                    *expr = Expr::IntegerLiteral(IntegerLiteralExpression {
+                        token: *token,
+                        span:  *span,
+                        value: l.value + r.value,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut prog = parse_from_string(
+        "x = 1 + 2 + y
+print((3 + 4) * 5)",
+    )
+    .unwrap();
+    FoldAdditions.visit_program_mut(&mut prog);
+    assert_eq!(prog.statements[0].pretty_print(), "x = 3 + y");
+    assert_eq!(prog.statements[1].pretty_print(), "print(7 * 5)");
+}
+
+#[test]
+fn test_visitor_mut_renames() {
+    /// Renames `Pet` to `Animal` wherever it is used or declared
+    struct Rename;
+    impl<'a> VisitorMut<'a> for Rename {
+        fn visit_identifier_mut(&mut self, ident: &mut Identifier<'a>) {
+            if ident.get_ident() == "Pet" {
+                ident.token = Token::Identifier("Animal");
+            }
+        }
+    }
+
+    let mut prog = parse_from_string(
+        "class Pet
+endclass
+class Dog inherits Pet
+endclass
+function Pet(Pet)
+    array Pet[Pet]
+    return new Pet().Pet.Pet(Pet(Pet))
+endfunction",
+    )
+    .unwrap();
+    Rename.visit_program_mut(&mut prog);
+    let printed = prog
+        .statements
+        .iter()
+        .map(|s| s.pretty_print())
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert!(!printed.contains("Pet"), "{}", printed);
+}
+
+#[test]
+fn test_into_owned() {
+    let source = String::from(
+        "class Greeter
+    private greeting
+    public procedure new(g)
+        greeting = g
+    endprocedure
+endclass
+if x == \"hi\" then
+    print(new Greeter(x).greeting)
+elseif x > 1.5 then
+    y = -x[0]
+endif",
+    );
+    let prog = parse_from_string(&source).unwrap();
+    let expected = prog
+        .statements
+        .iter()
+        .map(|s| s.pretty_print())
+        .collect::<Vec<_>>();
+    let interner = Interner::new();
+    let owned = prog.clone().into_owned(&interner);
+    assert_eq!(owned.as_borrowed(), prog);
+    drop(prog);
+    drop(source);
+    drop(interner);
+
+    // Nothing is borrowed, so it can be moved to a thread which may outlive
+    // this function
+    let printed = std::thread::spawn(move || {
+        owned
+            .as_borrowed()
+            .statements
+            .iter()
+            .map(|s| s.pretty_print())
+            .collect::<Vec<_>>()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(printed, expected);
+}
@@ -0,0 +1,692 @@
+//! Traversal of the AST. Each `visit_*` method on `Visitor` and `VisitorMut`
+//! defaults to calling the matching `walk_*` function, which visits the
+//! children of the node. Overriding a method and calling the `walk_*` function
+//! from it visits the node while still going into its children.
+//!
+//! Nodes without children, like literals, have a `visit_*` method which does
+//! nothing by default and no `walk_*` function.
+
+use super::ArrayDeclarationStatement;
+use super::AssignStatement;
+use super::AssignTarget;
+use super::BlockStatement;
+use super::BooleanExpression;
+use super::ClassMethod;
+use super::ClassStatement;
+use super::DoUntilStatement;
+use super::ErrorStatement;
+use super::Expr;
+use super::ExpressionStatement;
+use super::ForStatement;
+use super::FunctionCallExpression;
+use super::FunctionStatement;
+use super::Identifier;
+use super::IfStatement;
+use super::IndexExpression;
+use super::InfixExpression;
+use super::IntegerLiteralExpression;
+use super::MemberAccessExpression;
+use super::MethodCallExpression;
+use super::NewExpression;
+use super::PlaceholderExpression;
+use super::PrefixExpression;
+use super::RealLiteralExpression;
+use super::ReturnStatement;
+use super::Stmt;
+use super::StringLiteralExpression;
+use super::SuperExpression;
+use super::SwitchCase;
+use super::SwitchStatement;
+use super::WhileStatement;
+use crate::parser::Program;
+
+/// Visits the nodes of an AST by reference. The nodes are borrowed for as
+/// long as the AST, so a visitor can hold on to them.
+pub trait Visitor<'a>: Sized {
+    fn visit_program(&mut self, prog: &'a Program<'a>) {
+        walk_program(self, prog);
+    }
+
+    fn visit_stmt(&mut self, stmt: &'a Stmt<'a>) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_assign(&mut self, stmt: &'a AssignStatement<'a>) {
+        walk_assign(self, stmt);
+    }
+
+    fn visit_assign_target(&mut self, target: &'a AssignTarget<'a>) {
+        walk_assign_target(self, target);
+    }
+
+    fn visit_return(&mut self, stmt: &'a ReturnStatement<'a>) {
+        walk_return(self, stmt);
+    }
+
+    fn visit_expression_statement(&mut self, stmt: &'a ExpressionStatement<'a>) {
+        walk_expression_statement(self, stmt);
+    }
+
+    fn visit_if(&mut self, stmt: &'a IfStatement<'a>) {
+        walk_if(self, stmt);
+    }
+
+    fn visit_block(&mut self, block: &'a BlockStatement<'a>) {
+        walk_block(self, block);
+    }
+
+    fn visit_function(&mut self, stmt: &'a FunctionStatement<'a>) {
+        walk_function(self, stmt);
+    }
+
+    fn visit_for(&mut self, stmt: &'a ForStatement<'a>) {
+        walk_for(self, stmt);
+    }
+
+    fn visit_while(&mut self, stmt: &'a WhileStatement<'a>) {
+        walk_while(self, stmt);
+    }
+
+    fn visit_do_until(&mut self, stmt: &'a DoUntilStatement<'a>) {
+        walk_do_until(self, stmt);
+    }
+
+    fn visit_switch(&mut self, stmt: &'a SwitchStatement<'a>) {
+        walk_switch(self, stmt);
+    }
+
+    fn visit_switch_case(&mut self, case: &'a SwitchCase<'a>) {
+        walk_switch_case(self, case);
+    }
+
+    fn visit_error(&mut self, _stmt: &'a ErrorStatement) {}
+
+    fn visit_array_declaration(&mut self, stmt: &'a ArrayDeclarationStatement<'a>) {
+        walk_array_declaration(self, stmt);
+    }
+
+    fn visit_class(&mut self, stmt: &'a ClassStatement<'a>) {
+        walk_class(self, stmt);
+    }
+
+    fn visit_class_method(&mut self, method: &'a ClassMethod<'a>) {
+        walk_class_method(self, method);
+    }
+
+    fn visit_expr(&mut self, expr: &'a Expr<'a>) {
+        walk_expr(self, expr);
+    }
+
+    /// Called for every name: variables whether they are read or assigned to,
+    /// and the names of functions, parameters, arrays, classes, attributes,
+    /// methods and members where they are declared or used
+    fn visit_identifier(&mut self, _ident: &'a Identifier<'a>) {}
+
+    fn visit_boolean(&mut self, _expr: &'a BooleanExpression<'a>) {}
+
+    fn visit_placeholder(&mut self, _expr: &'a PlaceholderExpression) {}
+
+    fn visit_integer_literal(&mut self, _expr: &'a IntegerLiteralExpression<'a>) {}
+
+    fn visit_real_literal(&mut self, _expr: &'a RealLiteralExpression<'a>) {}
+
+    fn visit_string_literal(&mut self, _expr: &'a StringLiteralExpression<'a>) {}
+
+    fn visit_prefix(&mut self, expr: &'a PrefixExpression<'a>) {
+        walk_prefix(self, expr);
+    }
+
+    fn visit_infix(&mut self, expr: &'a InfixExpression<'a>) {
+        walk_infix(self, expr);
+    }
+
+    fn visit_function_call(&mut self, expr: &'a FunctionCallExpression<'a>) {
+        walk_function_call(self, expr);
+    }
+
+    fn visit_index(&mut self, expr: &'a IndexExpression<'a>) {
+        walk_index(self, expr);
+    }
+
+    fn visit_new(&mut self, expr: &'a NewExpression<'a>) {
+        walk_new(self, expr);
+    }
+
+    fn visit_super(&mut self, _expr: &'a SuperExpression<'a>) {}
+
+    fn visit_member_access(&mut self, expr: &'a MemberAccessExpression<'a>) {
+        walk_member_access(self, expr);
+    }
+
+    fn visit_method_call(&mut self, expr: &'a MethodCallExpression<'a>) {
+        walk_method_call(self, expr);
+    }
+}
+
+pub fn walk_program<'a, V: Visitor<'a>>(v: &mut V, prog: &'a Program<'a>) {
+    for stmt in &prog.statements {
+        v.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a Stmt<'a>) {
+    match stmt {
+        Stmt::Assign(s) => v.visit_assign(s),
+        Stmt::Return(s) => v.visit_return(s),
+        Stmt::Expression(s) => v.visit_expression_statement(s),
+        Stmt::If(s) => v.visit_if(s),
+        Stmt::Block(s) => v.visit_block(s),
+        Stmt::Function(s) => v.visit_function(s),
+        Stmt::For(s) => v.visit_for(s),
+        Stmt::While(s) => v.visit_while(s),
+        Stmt::DoUntil(s) => v.visit_do_until(s),
+        Stmt::Switch(s) => v.visit_switch(s),
+        Stmt::Error(s) => v.visit_error(s),
+        Stmt::ArrayDeclaration(s) => v.visit_array_declaration(s),
+        Stmt::Class(s) => v.visit_class(s),
+        Stmt::Empty => (),
+    }
+}
+
+/// The value is visited before the target, the order they are evaluated in
+pub fn walk_assign<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a AssignStatement<'a>) {
+    v.visit_expr(&stmt.value);
+    v.visit_assign_target(&stmt.target);
+}
+
+pub fn walk_assign_target<'a, V: Visitor<'a>>(v: &mut V, target: &'a AssignTarget<'a>) {
+    match target {
+        AssignTarget::Identifier(i) => v.visit_identifier(i),
+        AssignTarget::Index(i) => v.visit_index(i),
+        AssignTarget::Member(m) => v.visit_member_access(m),
+    }
+}
+
+pub fn walk_return<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a ReturnStatement<'a>) {
+    if let Some(value) = &stmt.value {
+        v.visit_expr(value);
+    }
+}
+
+pub fn walk_expression_statement<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a ExpressionStatement<'a>) {
+    v.visit_expr(&stmt.value);
+}
+
+pub fn walk_if<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a IfStatement<'a>) {
+    v.visit_expr(&stmt.condition);
+    v.visit_block(&stmt.consequence);
+    for (condition, block) in &stmt.elseifs {
+        v.visit_expr(condition);
+        v.visit_block(block);
+    }
+    if let Some(alt) = &stmt.alternative {
+        v.visit_block(alt);
+    }
+}
+
+pub fn walk_block<'a, V: Visitor<'a>>(v: &mut V, block: &'a BlockStatement<'a>) {
+    for stmt in &block.statements {
+        v.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_function<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a FunctionStatement<'a>) {
+    v.visit_identifier(&stmt.ident);
+    for param in &stmt.params {
+        v.visit_identifier(&param.ident);
+    }
+    v.visit_block(&stmt.body);
+}
+
+pub fn walk_for<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a ForStatement<'a>) {
+    v.visit_identifier(&stmt.counter);
+    v.visit_expr(&stmt.start);
+    v.visit_expr(&stmt.end);
+    if let Some(step) = &stmt.step {
+        v.visit_expr(step);
+    }
+    v.visit_block(&stmt.body);
+}
+
+pub fn walk_while<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a WhileStatement<'a>) {
+    v.visit_expr(&stmt.condition);
+    v.visit_block(&stmt.body);
+}
+
+pub fn walk_do_until<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a DoUntilStatement<'a>) {
+    v.visit_block(&stmt.body);
+    v.visit_expr(&stmt.condition);
+}
+
+pub fn walk_switch<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a SwitchStatement<'a>) {
+    v.visit_expr(&stmt.subject);
+    for case in &stmt.cases {
+        v.visit_switch_case(case);
+    }
+    if let Some(default) = &stmt.default {
+        v.visit_block(default);
+    }
+}
+
+pub fn walk_switch_case<'a, V: Visitor<'a>>(v: &mut V, case: &'a SwitchCase<'a>) {
+    v.visit_expr(&case.value);
+    v.visit_block(&case.body);
+}
+
+pub fn walk_array_declaration<'a, V: Visitor<'a>>(
+    v: &mut V,
+    stmt: &'a ArrayDeclarationStatement<'a>,
+) {
+    v.visit_identifier(&stmt.ident);
+    for size in &stmt.sizes {
+        v.visit_expr(size);
+    }
+}
+
+pub fn walk_class<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a ClassStatement<'a>) {
+    v.visit_identifier(&stmt.ident);
+    if let Some(parent) = &stmt.parent {
+        v.visit_identifier(parent);
+    }
+    for attribute in &stmt.attributes {
+        v.visit_identifier(&attribute.ident);
+    }
+    for method in &stmt.methods {
+        v.visit_class_method(method);
+    }
+}
+
+pub fn walk_class_method<'a, V: Visitor<'a>>(v: &mut V, method: &'a ClassMethod<'a>) {
+    v.visit_function(&method.func);
+}
+
+pub fn walk_expr<'a, V: Visitor<'a>>(v: &mut V, expr: &'a Expr<'a>) {
+    match expr {
+        Expr::Identifier(e) => v.visit_identifier(e),
+        Expr::Boolean(e) => v.visit_boolean(e),
+        Expr::Placeholder(e) => v.visit_placeholder(e),
+        Expr::IntegerLiteral(e) => v.visit_integer_literal(e),
+        Expr::RealLiteral(e) => v.visit_real_literal(e),
+        Expr::StringLiteral(e) => v.visit_string_literal(e),
+        Expr::Prefix(e) => v.visit_prefix(e),
+        Expr::Infix(e) => v.visit_infix(e),
+        Expr::FunctionCall(e) => v.visit_function_call(e),
+        Expr::Index(e) => v.visit_index(e),
+        Expr::New(e) => v.visit_new(e),
+        Expr::Super(e) => v.visit_super(e),
+        Expr::MemberAccess(e) => v.visit_member_access(e),
+        Expr::MethodCall(e) => v.visit_method_call(e),
+    }
+}
+
+pub fn walk_prefix<'a, V: Visitor<'a>>(v: &mut V, expr: &'a PrefixExpression<'a>) {
+    v.visit_expr(&expr.subject);
+}
+
+pub fn walk_infix<'a, V: Visitor<'a>>(v: &mut V, expr: &'a InfixExpression<'a>) {
+    v.visit_expr(&expr.left);
+    v.visit_expr(&expr.right);
+}
+
+pub fn walk_function_call<'a, V: Visitor<'a>>(v: &mut V, expr: &'a FunctionCallExpression<'a>) {
+    v.visit_identifier(&expr.func);
+    for arg in &expr.args {
+        v.visit_expr(arg);
+    }
+}
+
+pub fn walk_index<'a, V: Visitor<'a>>(v: &mut V, expr: &'a IndexExpression<'a>) {
+    v.visit_expr(&expr.subject);
+    for index in &expr.indices {
+        v.visit_expr(index);
+    }
+}
+
+pub fn walk_new<'a, V: Visitor<'a>>(v: &mut V, expr: &'a NewExpression<'a>) {
+    v.visit_identifier(&expr.class);
+    for arg in &expr.args {
+        v.visit_expr(arg);
+    }
+}
+
+pub fn walk_member_access<'a, V: Visitor<'a>>(v: &mut V, expr: &'a MemberAccessExpression<'a>) {
+    v.visit_expr(&expr.subject);
+    v.visit_identifier(&expr.member);
+}
+
+pub fn walk_method_call<'a, V: Visitor<'a>>(v: &mut V, expr: &'a MethodCallExpression<'a>) {
+    v.visit_expr(&expr.subject);
+    v.visit_identifier(&expr.method);
+    for arg in &expr.args {
+        v.visit_expr(arg);
+    }
+}
+
+/// Like `Visitor`, but with mutable access to each node so that passes can
+/// rewrite the AST in place. A whole `Stmt` or `Expr` can be replaced from
+/// `visit_stmt_mut` or `visit_expr_mut`.
+pub trait VisitorMut<'a>: Sized {
+    fn visit_program_mut(&mut self, prog: &mut Program<'a>) {
+        walk_program_mut(self, prog);
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt<'a>) {
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_assign_mut(&mut self, stmt: &mut AssignStatement<'a>) {
+        walk_assign_mut(self, stmt);
+    }
+
+    fn visit_assign_target_mut(&mut self, target: &mut AssignTarget<'a>) {
+        walk_assign_target_mut(self, target);
+    }
+
+    fn visit_return_mut(&mut self, stmt: &mut ReturnStatement<'a>) {
+        walk_return_mut(self, stmt);
+    }
+
+    fn visit_expression_statement_mut(&mut self, stmt: &mut ExpressionStatement<'a>) {
+        walk_expression_statement_mut(self, stmt);
+    }
+
+    fn visit_if_mut(&mut self, stmt: &mut IfStatement<'a>) {
+        walk_if_mut(self, stmt);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut BlockStatement<'a>) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_function_mut(&mut self, stmt: &mut FunctionStatement<'a>) {
+        walk_function_mut(self, stmt);
+    }
+
+    fn visit_for_mut(&mut self, stmt: &mut ForStatement<'a>) {
+        walk_for_mut(self, stmt);
+    }
+
+    fn visit_while_mut(&mut self, stmt: &mut WhileStatement<'a>) {
+        walk_while_mut(self, stmt);
+    }
+
+    fn visit_do_until_mut(&mut self, stmt: &mut DoUntilStatement<'a>) {
+        walk_do_until_mut(self, stmt);
+    }
+
+    fn visit_switch_mut(&mut self, stmt: &mut SwitchStatement<'a>) {
+        walk_switch_mut(self, stmt);
+    }
+
+    fn visit_switch_case_mut(&mut self, case: &mut SwitchCase<'a>) {
+        walk_switch_case_mut(self, case);
+    }
+
+    fn visit_error_mut(&mut self, _stmt: &mut ErrorStatement) {}
+
+    fn visit_array_declaration_mut(&mut self, stmt: &mut ArrayDeclarationStatement<'a>) {
+        walk_array_declaration_mut(self, stmt);
+    }
+
+    fn visit_class_mut(&mut self, stmt: &mut ClassStatement<'a>) {
+        walk_class_mut(self, stmt);
+    }
+
+    fn visit_class_method_mut(&mut self, method: &mut ClassMethod<'a>) {
+        walk_class_method_mut(self, method);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr<'a>) {
+        walk_expr_mut(self, expr);
+    }
+
+    /// Called for every name, the same as `Visitor::visit_identifier`
+    fn visit_identifier_mut(&mut self, _ident: &mut Identifier<'a>) {}
+
+    fn visit_boolean_mut(&mut self, _expr: &mut BooleanExpression<'a>) {}
+
+    fn visit_placeholder_mut(&mut self, _expr: &mut PlaceholderExpression) {}
+
+    fn visit_integer_literal_mut(&mut self, _expr: &mut IntegerLiteralExpression<'a>) {}
+
+    fn visit_real_literal_mut(&mut self, _expr: &mut RealLiteralExpression<'a>) {}
+
+    fn visit_string_literal_mut(&mut self, _expr: &mut StringLiteralExpression<'a>) {}
+
+    fn visit_prefix_mut(&mut self, expr: &mut PrefixExpression<'a>) {
+        walk_prefix_mut(self, expr);
+    }
+
+    fn visit_infix_mut(&mut self, expr: &mut InfixExpression<'a>) {
+        walk_infix_mut(self, expr);
+    }
+
+    fn visit_function_call_mut(&mut self, expr: &mut FunctionCallExpression<'a>) {
+        walk_function_call_mut(self, expr);
+    }
+
+    fn visit_index_mut(&mut self, expr: &mut IndexExpression<'a>) {
+        walk_index_mut(self, expr);
+    }
+
+    fn visit_new_mut(&mut self, expr: &mut NewExpression<'a>) {
+        walk_new_mut(self, expr);
+    }
+
+    fn visit_super_mut(&mut self, _expr: &mut SuperExpression<'a>) {}
+
+    fn visit_member_access_mut(&mut self, expr: &mut MemberAccessExpression<'a>) {
+        walk_member_access_mut(self, expr);
+    }
+
+    fn visit_method_call_mut(&mut self, expr: &mut MethodCallExpression<'a>) {
+        walk_method_call_mut(self, expr);
+    }
+}
+
+pub fn walk_program_mut<'a, V: VisitorMut<'a>>(v: &mut V, prog: &mut Program<'a>) {
+    for stmt in &mut prog.statements {
+        v.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_stmt_mut<'a, V: VisitorMut<'a>>(v: &mut V, stmt: &mut Stmt<'a>) {
+    match stmt {
+        Stmt::Assign(s) => v.visit_assign_mut(s),
+        Stmt::Return(s) => v.visit_return_mut(s),
+        Stmt::Expression(s) => v.visit_expression_statement_mut(s),
+        Stmt::If(s) => v.visit_if_mut(s),
+        Stmt::Block(s) => v.visit_block_mut(s),
+        Stmt::Function(s) => v.visit_function_mut(s),
+        Stmt::For(s) => v.visit_for_mut(s),
+        Stmt::While(s) => v.visit_while_mut(s),
+        Stmt::DoUntil(s) => v.visit_do_until_mut(s),
+        Stmt::Switch(s) => v.visit_switch_mut(s),
+        Stmt::Error(s) => v.visit_error_mut(s),
+        Stmt::ArrayDeclaration(s) => v.visit_array_declaration_mut(s),
+        Stmt::Class(s) => v.visit_class_mut(s),
+        Stmt::Empty => (),
+    }
+}
+
+pub fn walk_assign_mut<'a, V: VisitorMut<'a>>(v: &mut V, stmt: &mut AssignStatement<'a>) {
+    v.visit_expr_mut(&mut stmt.value);
+    v.visit_assign_target_mut(&mut stmt.target);
+}
+
+pub fn walk_assign_target_mut<'a, V: VisitorMut<'a>>(v: &mut V, target: &mut AssignTarget<'a>) {
+    match target {
+        AssignTarget::Identifier(i) => v.visit_identifier_mut(i),
+        AssignTarget::Index(i) => v.visit_index_mut(i),
+        AssignTarget::Member(m) => v.visit_member_access_mut(m),
+    }
+}
+
+pub fn walk_return_mut<'a, V: VisitorMut<'a>>(v: &mut V, stmt: &mut ReturnStatement<'a>) {
+    if let Some(value) = &mut stmt.value {
+        v.visit_expr_mut(value);
+    }
+}
+
+pub fn walk_expression_statement_mut<'a, V: VisitorMut<'a>>(
+    v: &mut V,
+    stmt: &mut ExpressionStatement<'a>,
+) {
+    v.visit_expr_mut(&mut stmt.value);
+}
+
+pub fn walk_if_mut<'a, V: VisitorMut<'a>>(v: &mut V, stmt: &mut IfStatement<'a>) {
+    v.visit_expr_mut(&mut stmt.condition);
+    v.visit_block_mut(&mut stmt.consequence);
+    for (condition, block) in &mut stmt.elseifs {
+        v.visit_expr_mut(condition);
+        v.visit_block_mut(block);
+    }
+    if let Some(alt) = &mut stmt.alternative {
+        v.visit_block_mut(alt);
+    }
+}
+
+pub fn walk_block_mut<'a, V: VisitorMut<'a>>(v: &mut V, block: &mut BlockStatement<'a>) {
+    for stmt in &mut block.statements {
+        v.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_function_mut<'a, V: VisitorMut<'a>>(v: &mut V, stmt: &mut FunctionStatement<'a>) {
+    v.visit_identifier_mut(&mut stmt.ident);
+    for param in &mut stmt.params {
+        v.visit_identifier_mut(&mut param.ident);
+    }
+    v.visit_block_mut(&mut stmt.body);
+}
+
+pub fn walk_for_mut<'a, V: VisitorMut<'a>>(v: &mut V, stmt: &mut ForStatement<'a>) {
+    v.visit_identifier_mut(&mut stmt.counter);
+    v.visit_expr_mut(&mut stmt.start);
+    v.visit_expr_mut(&mut stmt.end);
+    if let Some(step) = &mut stmt.step {
+        v.visit_expr_mut(step);
+    }
+    v.visit_block_mut(&mut stmt.body);
+}
+
+pub fn walk_while_mut<'a, V: VisitorMut<'a>>(v: &mut V, stmt: &mut WhileStatement<'a>) {
+    v.visit_expr_mut(&mut stmt.condition);
+    v.visit_block_mut(&mut stmt.body);
+}
+
+pub fn walk_do_until_mut<'a, V: VisitorMut<'a>>(v: &mut V, stmt: &mut DoUntilStatement<'a>) {
+    v.visit_block_mut(&mut stmt.body);
+    v.visit_expr_mut(&mut stmt.condition);
+}
+
+pub fn walk_switch_mut<'a, V: VisitorMut<'a>>(v: &mut V, stmt: &mut SwitchStatement<'a>) {
+    v.visit_expr_mut(&mut stmt.subject);
+    for case in &mut stmt.cases {
+        v.visit_switch_case_mut(case);
+    }
+    if let Some(default) = &mut stmt.default {
+        v.visit_block_mut(default);
+    }
+}
+
+pub fn walk_switch_case_mut<'a, V: VisitorMut<'a>>(v: &mut V, case: &mut SwitchCase<'a>) {
+    v.visit_expr_mut(&mut case.value);
+    v.visit_block_mut(&mut case.body);
+}
+
+pub fn walk_array_declaration_mut<'a, V: VisitorMut<'a>>(
+    v: &mut V,
+    stmt: &mut ArrayDeclarationStatement<'a>,
+) {
+    v.visit_identifier_mut(&mut stmt.ident);
+    for size in &mut stmt.sizes {
+        v.visit_expr_mut(size);
+    }
+}
+
+pub fn walk_class_mut<'a, V: VisitorMut<'a>>(v: &mut V, stmt: &mut ClassStatement<'a>) {
+    v.visit_identifier_mut(&mut stmt.ident);
+    if let Some(parent) = &mut stmt.parent {
+        v.visit_identifier_mut(parent);
+    }
+    for attribute in &mut stmt.attributes {
+        v.visit_identifier_mut(&mut attribute.ident);
+    }
+    for method in &mut stmt.methods {
+        v.visit_class_method_mut(method);
+    }
+}
+
+pub fn walk_class_method_mut<'a, V: VisitorMut<'a>>(v: &mut V, method: &mut ClassMethod<'a>) {
+    v.visit_function_mut(&mut method.func);
+}
+
+pub fn walk_expr_mut<'a, V: VisitorMut<'a>>(v: &mut V, expr: &mut Expr<'a>) {
+    match expr {
+        Expr::Identifier(e) => v.visit_identifier_mut(e),
+        Expr::Boolean(e) => v.visit_boolean_mut(e),
+        Expr::Placeholder(e) => v.visit_placeholder_mut(e),
+        Expr::IntegerLiteral(e) => v.visit_integer_literal_mut(e),
+        Expr::RealLiteral(e) => v.visit_real_literal_mut(e),
+        Expr::StringLiteral(e) => v.visit_string_literal_mut(e),
+        Expr::Prefix(e) => v.visit_prefix_mut(e),
+        Expr::Infix(e) => v.visit_infix_mut(e),
+        Expr::FunctionCall(e) => v.visit_function_call_mut(e),
+        Expr::Index(e) => v.visit_index_mut(e),
+        Expr::New(e) => v.visit_new_mut(e),
+        Expr::Super(e) => v.visit_super_mut(e),
+        Expr::MemberAccess(e) => v.visit_member_access_mut(e),
+        Expr::MethodCall(e) => v.visit_method_call_mut(e),
+    }
+}
+
+pub fn walk_prefix_mut<'a, V: VisitorMut<'a>>(v: &mut V, expr: &mut PrefixExpression<'a>) {
+    v.visit_expr_mut(&mut expr.subject);
+}
+
+pub fn walk_infix_mut<'a, V: VisitorMut<'a>>(v: &mut V, expr: &mut InfixExpression<'a>) {
+    v.visit_expr_mut(&mut expr.left);
+    v.visit_expr_mut(&mut expr.right);
+}
+
+pub fn walk_function_call_mut<'a, V: VisitorMut<'a>>(
+    v: &mut V,
+    expr: &mut FunctionCallExpression<'a>,
+) {
+    v.visit_identifier_mut(&mut expr.func);
+    for arg in &mut expr.args {
+        v.visit_expr_mut(arg);
+    }
+}
+
+pub fn walk_index_mut<'a, V: VisitorMut<'a>>(v: &mut V, expr: &mut IndexExpression<'a>) {
+    v.visit_expr_mut(&mut expr.subject);
+    for index in &mut expr.indices {
+        v.visit_expr_mut(index);
+    }
+}
+
+pub fn walk_new_mut<'a, V: VisitorMut<'a>>(v: &mut V, expr: &mut NewExpression<'a>) {
+    v.visit_identifier_mut(&mut expr.class);
+    for arg in &mut expr.args {
+        v.visit_expr_mut(arg);
+    }
+}
+
+pub fn walk_member_access_mut<'a, V: VisitorMut<'a>>(
+    v: &mut V,
+    expr: &mut MemberAccessExpression<'a>,
+) {
+    v.visit_expr_mut(&mut expr.subject);
+    v.visit_identifier_mut(&mut expr.member);
+}
+
+pub fn walk_method_call_mut<'a, V: VisitorMut<'a>>(v: &mut V, expr: &mut MethodCallExpression<'a>) {
+    v.visit_expr_mut(&mut expr.subject);
+    v.visit_identifier_mut(&mut expr.method);
+    for arg in &mut expr.args {
+        v.visit_expr_mut(arg);
+    }
+}